@@ -6,5 +6,5 @@ fn main() {
     let ma = ModuleAnalysis::new(&m);
 
     let fa = ma.fn_analysis("main");
-    let ca = fa.control_dependence_graph();
+    let _ca = fa.control_dependence_graph();
 }
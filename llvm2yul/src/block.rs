@@ -3,16 +3,20 @@ use std::collections::BTreeSet;
 use anyhow::{anyhow, Result};
 use llvm_ir::{
     instruction::{
-        Alloca, Call, ExtractValue, GetElementPtr, InsertValue, IntToPtr, Phi, PtrToInt, Select,
+        Alloca, BinaryOp, Call, ExtractValue, Freeze, GetElementPtr, ICmp, InsertValue, IntToPtr,
+        LandingPad, Load, PtrToInt, Select, Store,
     },
+    terminator::Invoke,
     types::Types,
-    BasicBlock, Instruction,
+    BasicBlock, Instruction, Name, Operand, Terminator, Type,
 };
 use yuler::Statement;
 
 use crate::{
-    AllocaCompiler, CallCompiler, Config, ExtractValueCompiler, GetElementPtrCompiler,
-    PtrIntCompiler, SelectCompiler,
+    intrinsic_name, AllocaCompiler, BinaryCompiler, BinaryOperator, CallCompiler, CastCompiler,
+    CastOperator, Config, ExtractValueCompiler, FreezeCompiler, GetElementPtrCompiler,
+    ICmpCompiler, InsertValueCompiler, IntrinsicCompiler, LandingPadCompiler, LoadCompiler,
+    PtrIntCompiler, SelectCompiler, StoreCompiler,
 };
 
 pub struct BlockCompiler<'a> {
@@ -20,6 +24,7 @@ pub struct BlockCompiler<'a> {
     llvm_types: &'a Types,
     config: &'a Config,
     pub(crate) objects: BTreeSet<String>,
+    pub(crate) helpers: BTreeSet<String>,
 }
 
 impl<'a> BlockCompiler<'a> {
@@ -30,6 +35,7 @@ impl<'a> BlockCompiler<'a> {
             config,
 
             objects: Default::default(),
+            helpers: Default::default(),
         }
     }
 
@@ -41,6 +47,11 @@ impl<'a> BlockCompiler<'a> {
             stmts.append(&mut i);
         }
 
+        // Branch of `invoke` is built by `ControlFlowBuilder`, only the call is compiled here.
+        if let Terminator::Invoke(t) = &self.bb.term {
+            stmts.append(&mut self.compile_invoke(t)?);
+        }
+
         Ok(stmts)
     }
 
@@ -49,22 +60,47 @@ impl<'a> BlockCompiler<'a> {
             Instruction::ExtractValue(i) => self.compile_extract_value(i)?,
             Instruction::InsertValue(i) => self.compile_insert_value(i)?,
             Instruction::Alloca(i) => self.compile_alloca(i)?,
-            Instruction::Phi(i) => self.compile_phi(i)?,
+            // Phi is destructed on incoming edges by `PhiCompiler`
+            Instruction::Phi(_) => vec![],
+            Instruction::Call(i) if intrinsic_name(i).is_some() => self.compile_intrinsic(i)?,
             Instruction::Call(i) => self.compile_call(i)?,
             Instruction::Select(i) => self.compile_select(i)?,
             Instruction::IntToPtr(i) => self.compile_int2ptr(i)?,
             Instruction::PtrToInt(i) => self.compile_ptr2int(i)?,
             Instruction::GetElementPtr(i) => self.compile_get_element_ptr(i)?,
+            Instruction::Load(i) => self.compile_load(i)?,
+            Instruction::Store(i) => self.compile_store(i)?,
+            Instruction::Trunc(i) => {
+                self.compile_cast(CastOperator::Trunc, &i.operand, &i.to_type, &i.dest)?
+            }
+            Instruction::ZExt(i) => {
+                self.compile_cast(CastOperator::ZExt, &i.operand, &i.to_type, &i.dest)?
+            }
+            Instruction::SExt(i) => {
+                self.compile_cast(CastOperator::SExt, &i.operand, &i.to_type, &i.dest)?
+            }
+            Instruction::ICmp(i) => self.compile_icmp(i)?,
+            Instruction::Add(i) => self.compile_binary(BinaryOperator::Add, i)?,
+            Instruction::Sub(i) => self.compile_binary(BinaryOperator::Sub, i)?,
+            Instruction::Mul(i) => self.compile_binary(BinaryOperator::Mul, i)?,
+            Instruction::UDiv(i) => self.compile_binary(BinaryOperator::UDiv, i)?,
+            Instruction::SDiv(i) => self.compile_binary(BinaryOperator::SDiv, i)?,
+            Instruction::URem(i) => self.compile_binary(BinaryOperator::URem, i)?,
+            Instruction::SRem(i) => self.compile_binary(BinaryOperator::SRem, i)?,
+            Instruction::Shl(i) => self.compile_binary(BinaryOperator::Shl, i)?,
+            Instruction::LShr(i) => self.compile_binary(BinaryOperator::LShr, i)?,
+            Instruction::AShr(i) => self.compile_binary(BinaryOperator::AShr, i)?,
+            Instruction::And(i) => self.compile_binary(BinaryOperator::And, i)?,
+            Instruction::Or(i) => self.compile_binary(BinaryOperator::Or, i)?,
+            Instruction::Xor(i) => self.compile_binary(BinaryOperator::Xor, i)?,
+            Instruction::Freeze(i) => self.compile_freeze(i)?,
+            Instruction::LandingPad(i) => self.compile_landing_pad(i)?,
             _ => return Err(anyhow!("Unsupported instruction: {}", inst)),
         };
 
         Ok(res)
     }
 
-    fn compile_phi(&self, _inst: &Phi) -> Result<Vec<Statement>> {
-        Ok(vec![])
-    }
-
     fn compile_call(&mut self, call: &Call) -> Result<Vec<Statement>> {
         let mut compiler = CallCompiler::new(call, self.llvm_types, self.config);
 
@@ -74,9 +110,51 @@ impl<'a> BlockCompiler<'a> {
             self.objects.insert(object);
         }
 
+        if let Some(dispatcher) = compiler.dispatcher {
+            self.helpers.insert(dispatcher);
+        }
+
         Ok(vec![stmt])
     }
 
+    fn compile_intrinsic(&mut self, call: &Call) -> Result<Vec<Statement>> {
+        let mut compiler = IntrinsicCompiler::new(call, self.llvm_types, self.config);
+
+        let stmts = compiler.compile()?;
+
+        self.helpers.append(&mut compiler.helpers);
+
+        Ok(stmts)
+    }
+
+    fn compile_invoke(&mut self, invoke: &Invoke) -> Result<Vec<Statement>> {
+        let returns_void = matches!(self.llvm_types.type_of(invoke).as_ref(), Type::VoidType);
+
+        let call = Call {
+            function: invoke.function.clone(),
+            function_ty: invoke.function_ty.clone(),
+            arguments: invoke.arguments.clone(),
+            return_attributes: invoke.return_attributes.clone(),
+            dest: (!returns_void).then(|| invoke.result.clone()),
+            function_attributes: invoke.function_attributes.clone(),
+            is_tail_call: false,
+            calling_convention: invoke.calling_convention,
+            debugloc: invoke.debugloc.clone(),
+        };
+
+        if intrinsic_name(&call).is_some() {
+            self.compile_intrinsic(&call)
+        } else {
+            self.compile_call(&call)
+        }
+    }
+
+    fn compile_landing_pad(&self, inst: &LandingPad) -> Result<Vec<Statement>> {
+        let compiler = LandingPadCompiler::new(inst, self.llvm_types, self.config);
+
+        compiler.compile()
+    }
+
     fn compile_alloca(&self, inst: &Alloca) -> Result<Vec<Statement>> {
         let compiler = AllocaCompiler::new(inst, self.llvm_types, self.config);
 
@@ -84,19 +162,21 @@ impl<'a> BlockCompiler<'a> {
     }
 
     fn compile_select(&self, inst: &Select) -> Result<Vec<Statement>> {
-        let select = SelectCompiler::new(inst);
+        let select = SelectCompiler::new(inst, self.llvm_types, self.config);
 
         select.compile()
     }
 
     fn compile_extract_value(&self, inst: &ExtractValue) -> Result<Vec<Statement>> {
-        let compiler = ExtractValueCompiler::new(inst);
+        let compiler = ExtractValueCompiler::new(inst, self.llvm_types, self.config);
 
         compiler.compile()
     }
 
-    fn compile_insert_value(&self, _inst: &InsertValue) -> Result<Vec<Statement>> {
-        Ok(vec![])
+    fn compile_insert_value(&self, inst: &InsertValue) -> Result<Vec<Statement>> {
+        let compiler = InsertValueCompiler::new(inst, self.llvm_types, self.config);
+
+        compiler.compile()
     }
 
     fn compile_int2ptr(&self, inst: &IntToPtr) -> Result<Vec<Statement>> {
@@ -112,7 +192,57 @@ impl<'a> BlockCompiler<'a> {
     }
 
     fn compile_get_element_ptr(&self, inst: &GetElementPtr) -> Result<Vec<Statement>> {
-        let compiler = GetElementPtrCompiler::new(inst);
+        let compiler = GetElementPtrCompiler::new(inst, self.llvm_types, self.config);
+
+        compiler.compile()
+    }
+
+    fn compile_load(&self, inst: &Load) -> Result<Vec<Statement>> {
+        let compiler = LoadCompiler::new(inst, self.llvm_types, self.config);
+
+        compiler.compile()
+    }
+
+    fn compile_store(&self, inst: &Store) -> Result<Vec<Statement>> {
+        let compiler = StoreCompiler::new(inst, self.llvm_types, self.config);
+
+        compiler.compile()
+    }
+
+    fn compile_binary(&self, op: BinaryOperator, inst: &impl BinaryOp) -> Result<Vec<Statement>> {
+        let compiler = BinaryCompiler::new(
+            op,
+            inst.get_operand0(),
+            inst.get_operand1(),
+            inst.get_result(),
+            self.llvm_types,
+            self.config,
+        );
+
+        compiler.compile()
+    }
+
+    fn compile_icmp(&self, inst: &ICmp) -> Result<Vec<Statement>> {
+        let compiler = ICmpCompiler::new(inst, self.llvm_types, self.config);
+
+        compiler.compile()
+    }
+
+    fn compile_freeze(&self, inst: &Freeze) -> Result<Vec<Statement>> {
+        let compiler = FreezeCompiler::new(inst, self.llvm_types, self.config);
+
+        compiler.compile()
+    }
+
+    fn compile_cast(
+        &self,
+        op: CastOperator,
+        operand: &Operand,
+        to_type: &Type,
+        dest: &Name,
+    ) -> Result<Vec<Statement>> {
+        let compiler = CastCompiler::new(op, operand, to_type, dest, self.llvm_types, self.config);
+
         compiler.compile()
     }
 }
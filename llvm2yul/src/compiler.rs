@@ -1,11 +1,15 @@
-use std::{collections::BTreeMap, path::Path};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
 
 use anyhow::{anyhow, Result};
-use llvm_ir::{types::Types, Function, Module};
+use llvm_ir::{module::GlobalVariable, types::Types, ConstantRef, Function, Module};
 use llvm_ir_analysis::ModuleAnalysis;
+use primitive_types::U256;
 use yuler::{FunctionCall, FunctionDefinition, Ident, Object};
 
-use crate::{utils, Config, FunctionCompiler};
+use crate::{utils, Config, FunctionCompiler, GlobalCompiler, TypeFlatter};
 
 #[derive(Debug, Default)]
 pub struct Compiler {
@@ -27,7 +31,7 @@ impl Compiler {
         &mut self,
         llvm_func: &Function,
         llvm_types: &Types,
-    ) -> Result<(FunctionDefinition, Vec<String>)> {
+    ) -> Result<(FunctionDefinition, Vec<String>, BTreeSet<String>)> {
         let mut func_compiler = FunctionCompiler::new(llvm_func, llvm_types, &self.config)?;
 
         func_compiler.compile_function_header()?;
@@ -35,20 +39,58 @@ impl Compiler {
         func_compiler.compile_function_body()
     }
 
+    fn dispatch_target(&self, name: &str, function: &FunctionDefinition) -> utils::DispatchTarget {
+        utils::DispatchTarget {
+            id: self
+                .config
+                .function_ids
+                .get(name)
+                .copied()
+                .unwrap_or_default(),
+            name: function.name.clone(),
+            args: function.args.len(),
+            rets: function.rets.len(),
+        }
+    }
+
     pub fn compile_object_from_bitcode(&mut self, path: &Path, entry: &str) -> Result<Object> {
-        let module =
-            Module::from_bc_path(path).map_err(|e| anyhow!("Failed to open module: {}", e))?;
+        let (ir, wide_constants) = utils::read_wide_ir(path)?;
 
-        self.compile_object(&module, entry)
+        self.compile_object_from_tagged_ir(&ir, wide_constants, entry)
     }
 
     pub fn compile_object_from_textir(&mut self, path: &Path, entry: &str) -> Result<Object> {
+        let (ir, wide_constants) = utils::read_wide_ir(path)?;
+
+        self.compile_object_from_tagged_ir(&ir, wide_constants, entry)
+    }
+
+    /// Compile textual IR, integer constants wider than 64 bits are kept.
+    pub fn compile_object_from_ir(&mut self, ir: &str, entry: &str) -> Result<Object> {
+        let (ir, wide_constants) = utils::tag_wide_constants(ir)?;
+
+        self.compile_object_from_tagged_ir(&ir, wide_constants, entry)
+    }
+
+    fn compile_object_from_tagged_ir(
+        &mut self,
+        ir: &str,
+        wide_constants: BTreeMap<u64, U256>,
+        entry: &str,
+    ) -> Result<Object> {
         let module =
-            Module::from_ir_path(path).map_err(|e| anyhow!("Failed to open module: {}", e))?;
+            Module::from_ir_str(ir).map_err(|e| anyhow!("Failed to open module: {}", e))?;
+
+        self.config.wide_constants = wide_constants;
 
         self.compile_object(&module, entry)
     }
 
+    /// Compile module which is already parsed by `llvm_ir`.
+    ///
+    /// Module isn't tagged by `tag_wide_constants`, so integer constants wider than 64 bits are
+    /// truncated to `u64`. Constant `getelementptr` isn't marked by `mark_const_geps` either, so
+    /// one with non-zero index is an error. Use other `compile_object_from_*` to keep both.
     pub fn compile_object(&mut self, module: &Module, entry: &str) -> Result<Object> {
         if let Some(object) = self.objects_caches.get(entry) {
             Ok(object.clone())
@@ -61,25 +103,74 @@ impl Compiler {
 
             let name = Ident::new(entry)?;
             let mut object = Object::new(name.clone());
+            let mut helpers = BTreeSet::new();
+            let mut targets = Vec::new();
+            let mut sub_objects = Vec::new();
+
+            self.config.function_ids = utils::function_ids(module);
+            self.config.data_globals = module
+                .global_vars
+                .iter()
+                .filter(|g| g.is_constant && g.initializer.is_some())
+                .map(|g| utils::yul_ident_name(&g.name))
+                .filter(|n| !utils::is_gep_marker(n))
+                .collect();
+            let used = utils::used_globals(module, |f| functions.contains(f) || f == entry);
+            self.config.static_addresses = self.layout_statics(module, &used)?;
+            self.config.gep_types = utils::gep_source_types(module);
+            self.config.const_strings = module
+                .global_vars
+                .iter()
+                .filter(|g| g.is_constant)
+                .filter_map(|g| {
+                    let bytes = utils::const_byte_string(g.initializer.as_ref()?)?;
+                    Some((utils::yul_ident_name(&g.name), bytes))
+                })
+                .collect();
 
             for func in &module.functions {
                 if let Some(function) = self.func_caches.get(&func.name) {
                     log::debug!("Hit Compiled function: {}", func.name);
 
+                    targets.push(self.dispatch_target(&func.name, function));
                     object.code.0.push(function.clone().into());
                 } else if functions.contains(&func.name) || func.name == entry {
                     log::debug!("Compile function: {}", func.name);
 
-                    let (function, objects) = self.compile_function(func, &module.types)?;
+                    let (function, objects, mut hs) = self.compile_function(func, &module.types)?;
 
+                    targets.push(self.dispatch_target(&func.name, &function));
                     object.code.0.push(function.into());
+                    helpers.append(&mut hs);
 
-                    for o in objects {
-                        object.objects.push(self.compile_object(module, &o)?);
-                    }
+                    sub_objects.extend(objects);
                 }
             }
 
+            let (mut image, end) = self.compile_statics(module)?;
+
+            let end = self.compile_globals(module, &mut object, &image, end)?;
+
+            let mut prologue = if end > self.config.static_base {
+                utils::build_statics_prologue(self.config.static_base, end, &mut image)?
+            } else {
+                Vec::new()
+            };
+
+            object.data.extend(utils::build_data_section(&image)?);
+
+            for helper in helpers {
+                let helper = if utils::dispatcher_arity(&helper).is_some() {
+                    utils::build_dispatcher(&helper, &targets)?
+                } else {
+                    utils::build_helper(&helper)?
+                };
+
+                object.code.0.push(helper.into());
+            }
+
+            object.code.0.append(&mut prologue);
+
             object.code.0.push(
                 FunctionCall {
                     name,
@@ -88,7 +179,202 @@ impl Compiler {
                 .into(),
             );
 
+            // Sub objects have their own statics, so they are compiled after this object is done.
+            for o in sub_objects {
+                object.objects.push(self.compile_object(module, &o)?);
+            }
+
             Ok(object)
         }
     }
+
+    /// Address of each mutable global in reserved region, which starts at word boundary.
+    ///
+    /// Only globals in `used` are placed, which are reachable from code of object.
+    fn layout_statics(
+        &self,
+        module: &Module,
+        used: &BTreeSet<String>,
+    ) -> Result<BTreeMap<String, u64>> {
+        let flatter = TypeFlatter::new(&module.types, &self.config);
+
+        let mut addresses = BTreeMap::new();
+        let mut address = self.config.static_base;
+
+        for (global, initializer) in statics(module) {
+            if !used.contains(&utils::yul_ident_name(&global.name)) {
+                continue;
+            }
+
+            let size = flatter.compute_size(&module.types.type_of(initializer))?;
+
+            addresses.insert(utils::yul_ident_name(&global.name), address);
+            address += size.div_ceil(32) * 32;
+        }
+
+        Ok(addresses)
+    }
+
+    /// Image of mutable globals in reserved region, and end of their region.
+    fn compile_statics(&self, module: &Module) -> Result<(utils::GlobalData, u64)> {
+        let base = self.config.static_base;
+
+        let mut image = utils::GlobalData {
+            name: utils::STATICS_DATA.into(),
+            bytes: Vec::new(),
+            relocations: Vec::new(),
+        };
+
+        for (global, _) in statics(module) {
+            let Some(address) = self
+                .config
+                .static_addresses
+                .get(&utils::yul_ident_name(&global.name))
+            else {
+                continue;
+            };
+
+            let offset = address - base;
+
+            let data = GlobalCompiler::new(global, &module.types, &self.config).serialize()?;
+
+            image.bytes.resize(offset as usize, 0);
+            image.bytes.extend(data.bytes);
+
+            for (o, value) in data.relocations {
+                image.relocations.push((offset + o, value));
+            }
+        }
+
+        let end = base + (image.bytes.len() as u64).div_ceil(32) * 32;
+
+        Ok((image, end))
+    }
+
+    /// Add data sections and loaders of constant globals which are used by code of object or
+    /// initializers of mutable globals.
+    ///
+    /// Each loader caches address of its global in a slot from `end`, returns end after slots.
+    fn compile_globals(
+        &self,
+        module: &Module,
+        object: &mut Object,
+        statics: &utils::GlobalData,
+        end: u64,
+    ) -> Result<u64> {
+        let mut pending = BTreeSet::new();
+
+        utils::called_functions(&object.code.0, &mut pending);
+
+        for (_, value) in &statics.relocations {
+            utils::value_calls(value, &mut pending);
+        }
+
+        let mut loaded = BTreeSet::new();
+        let mut slot = end;
+
+        while let Some(name) = pending.pop_first() {
+            let Some(global) = utils::loader_global(&name) else {
+                continue;
+            };
+
+            if !loaded.insert(global.to_string()) {
+                continue;
+            }
+
+            let var = module
+                .global_vars
+                .iter()
+                .find(|g| utils::yul_ident_name(&g.name) == global)
+                .ok_or(anyhow!("Unknown global: {global}"))?;
+
+            let (data, loader) =
+                GlobalCompiler::new(var, &module.types, &self.config).compile(slot)?;
+
+            utils::called_functions(&loader.block.0, &mut pending);
+
+            object.data.extend(data);
+            object.code.0.push(loader.into());
+
+            slot += 32;
+        }
+
+        Ok(slot)
+    }
+}
+
+/// Mutable globals with initializer, which live in reserved region.
+fn statics(module: &Module) -> impl Iterator<Item = (&GlobalVariable, &ConstantRef)> {
+    module
+        .global_vars
+        .iter()
+        .filter(|g| !g.is_constant)
+        .filter_map(|g| Some((g, g.initializer.as_ref()?)))
+}
+
+#[cfg(test)]
+mod tests {
+    use llvm_ir::Module;
+    use yuler::Writer;
+
+    use super::Compiler;
+    use crate::Config;
+
+    fn compile(ir: &str, entry: &str) -> String {
+        let object = Compiler::new(Config::default())
+            .compile_object_from_ir(ir, entry)
+            .unwrap();
+
+        let mut w = Writer::new(Vec::new(), "    ");
+        object.write(&mut w).unwrap();
+        String::from_utf8(w.w).unwrap()
+    }
+
+    const CONST_GEP: &str = r#"
+        @arr = global [4 x i64] [i64 1, i64 2, i64 3, i64 4]
+
+        define i64 @f() {
+          %a = load i64, ptr getelementptr ([4 x i64], ptr @arr, i64 0, i64 2)
+          ret i64 %a
+        }
+    "#;
+
+    #[test]
+    fn test_const_gep() {
+        let yul = compile(CONST_GEP, "f");
+
+        assert!(yul.contains("let a := mload(192)"), "{yul}");
+    }
+
+    #[test]
+    fn test_const_gep_without_marker() {
+        let module = Module::from_ir_str(CONST_GEP).unwrap();
+
+        let err = Compiler::new(Config::default())
+            .compile_object(&module, "f")
+            .unwrap_err();
+
+        assert!(err.to_string().contains("mark_const_geps"), "{err}");
+    }
+
+    #[test]
+    fn test_external_global() {
+        let ir = r#"
+            @ext = external global i64
+
+            define i64 @f() {
+              %a = load i64, ptr @ext
+              ret i64 %a
+            }
+        "#;
+
+        let err = Compiler::new(Config::default())
+            .compile_object_from_ir(ir, "f")
+            .unwrap_err();
+
+        assert!(
+            err.to_string().contains("global without address: ext"),
+            "{err}"
+        );
+    }
 }
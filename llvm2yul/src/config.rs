@@ -1,8 +1,87 @@
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
+
+use llvm_ir::{ConstantRef, TypeRef};
+use primitive_types::U256;
+
+/// Semantics of integers narrower than 256 bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IntSemantics {
+    /// Skip masking of `add`, `sub`, `mul` and `shl`, which don't wrap around at bit width of
+    /// LLVM type. Cheap, but unfaithful when they overflow. Other operations keep bit width.
+    Wide,
+    /// Mask or sign extend every result back to bit width of LLVM type.
+    #[default]
+    Exact,
+}
+
+/// Target EVM version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum EvmVersion {
+    Paris,
+    Shanghai,
+    #[default]
+    Cancun,
+    Prague,
+}
+
+impl EvmVersion {
+    /// `mcopy` is introduced in cancun.
+    pub fn has_mcopy(&self) -> bool {
+        *self >= Self::Cancun
+    }
+
+    /// `tload` and `tstore` are introduced in cancun.
+    pub fn has_transient(&self) -> bool {
+        *self >= Self::Cancun
+    }
+}
+
+/// How `invoke` and landing pads are lowered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnwindMode {
+    /// `invoke` is a call, landing pads are unreachable.
+    #[default]
+    Abort,
+    /// Callee of `invoke` returning to `unreachable` unwinds, cleanup runs then reverts.
+    Cleanup,
+}
+
+/// Data location of EVM, which pointers of an address space point to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataLocation {
+    Memory,
+    /// Address is slot, accessed by `sload` and `sstore`.
+    Storage,
+    /// Address is slot, accessed by `tload` and `tstore`.
+    Transient,
+    /// Read only, accessed by `calldataload`.
+    Calldata,
+    /// Read only, copied into scratch memory by `codecopy`.
+    Code,
+}
 
 #[derive(Debug)]
 pub struct Config {
     pub basic_types: BTreeSet<String>,
+    pub int_semantics: IntSemantics,
+    pub evm_version: EvmVersion,
+    pub unwind: UnwindMode,
+    pub address_spaces: BTreeMap<u32, DataLocation>,
+    /// Start of memory region reserved for mutable globals.
+    pub static_base: u64,
+    /// IDs of functions in module which is compiling, set by `Compiler`.
+    pub(crate) function_ids: BTreeMap<String, u64>,
+    /// Values of tagged wide integer constants, set by `Compiler`.
+    pub(crate) wide_constants: BTreeMap<u64, U256>,
+    /// Names of constant globals placed into data section, set by `Compiler`.
+    pub(crate) data_globals: BTreeSet<String>,
+    /// Addresses of mutable globals in reserved region, set by `Compiler`.
+    pub(crate) static_addresses: BTreeMap<String, u64>,
+    /// Byte strings of constant globals, used by `__yul__ext_literal_str`, set by `Compiler`.
+    pub(crate) const_strings: BTreeMap<String, Vec<u8>>,
+    /// Source element type and address of constant `getelementptr` by its marker, set by
+    /// `Compiler`.
+    pub(crate) gep_types: BTreeMap<String, (TypeRef, ConstantRef)>,
 }
 
 impl Config {
@@ -13,6 +92,53 @@ impl Config {
 
         self
     }
+
+    pub fn int_semantics(mut self, int_semantics: IntSemantics) -> Self {
+        self.int_semantics = int_semantics;
+
+        self
+    }
+
+    pub fn evm_version(mut self, evm_version: EvmVersion) -> Self {
+        self.evm_version = evm_version;
+
+        self
+    }
+
+    pub fn unwind(mut self, unwind: UnwindMode) -> Self {
+        self.unwind = unwind;
+
+        self
+    }
+
+    pub fn address_space(mut self, addr_space: u32, location: DataLocation) -> Self {
+        self.address_spaces.insert(addr_space, location);
+
+        self
+    }
+
+    pub fn static_base(mut self, base: u64) -> Self {
+        self.static_base = base;
+
+        self
+    }
+
+    /// Data location of pointers in `addr_space`, address space which isn't mapped is memory.
+    pub fn data_location(&self, addr_space: u32) -> DataLocation {
+        self.address_spaces
+            .get(&addr_space)
+            .copied()
+            .unwrap_or(DataLocation::Memory)
+    }
+
+    /// Bit width which results of `add`, `sub`, `mul` and `shl` on integer of `bits` bits wrap
+    /// around at.
+    pub fn wrap_bits(&self, bits: u32) -> u32 {
+        match self.int_semantics {
+            IntSemantics::Wide => 256,
+            IntSemantics::Exact => bits,
+        }
+    }
 }
 
 impl Default for Config {
@@ -26,6 +152,27 @@ impl Default for Config {
         basic_types.insert("patine_core::uint::U16".into());
         basic_types.insert("patine_core::uint::U8".into());
 
-        Self { basic_types }
+        let address_spaces = BTreeMap::from([
+            (1, DataLocation::Storage),
+            (2, DataLocation::Transient),
+            (3, DataLocation::Calldata),
+            (4, DataLocation::Code),
+        ]);
+
+        Self {
+            basic_types,
+            int_semantics: Default::default(),
+            evm_version: Default::default(),
+            unwind: Default::default(),
+            address_spaces,
+            // After scratch space, free memory pointer and zero slot of Solidity.
+            static_base: 0x80,
+            function_ids: Default::default(),
+            wide_constants: Default::default(),
+            data_globals: Default::default(),
+            static_addresses: Default::default(),
+            const_strings: Default::default(),
+            gep_types: Default::default(),
+        }
     }
 }
@@ -1,8 +1,12 @@
 use anyhow::{anyhow, Result};
-use llvm_ir::{types::Types, Constant, Type};
+use llvm_ir::{constant, types::Types, Constant, ConstantRef, Name, Operand, Type};
+use primitive_types::U256;
 use yuler::{Literal, Value};
 
-use crate::{error, utils, Config, TypeFlatter};
+use crate::{
+    build_binary, build_element_address, build_icmp, error, utils, BinaryOperator, Config,
+    TypeFlatter,
+};
 
 pub struct ConstantFlatter<'a> {
     types: &'a Types,
@@ -23,8 +27,14 @@ impl<'a> ConstantFlatter<'a> {
     }
 
     fn _flatten(&self, values: &mut Vec<Value>, constant: &Constant) -> Result<()> {
+        if let Some((op, operand0, operand1)) = binary_constant(constant) {
+            values.push(self._build_binary(op, operand0, operand1)?);
+
+            return Ok(());
+        }
+
         match constant {
-            Constant::Int { bits: _, value } => values.push(Literal::int_number(*value)?.into()),
+            Constant::Int { bits, value } => values.push(self._build_int(*bits, *value)?.into()),
             Constant::Null(_) => values.push(Literal::int_number(0)?.into()),
             Constant::AggregateZero(ty) => self._build_type_values(values, ty)?,
             Constant::Struct {
@@ -36,6 +46,12 @@ impl<'a> ConstantFlatter<'a> {
                     self._flatten(values, c)?
                 }
             }
+            Constant::Array {
+                element_type,
+                elements,
+            } if matches!(element_type.as_ref(), Type::IntegerType { bits: 8 }) => {
+                self._build_bytes(values, elements)?
+            }
             Constant::Array {
                 element_type: _,
                 elements,
@@ -48,8 +64,11 @@ impl<'a> ConstantFlatter<'a> {
             Constant::Poison(ty) => self._build_type_values(values, ty)?,
             Constant::PtrToInt(i) => self._flatten(values, &i.operand)?,
             Constant::IntToPtr(i) => self._flatten(values, &i.operand)?,
-            Constant::GlobalReference { name, ty: _ } => {
-                values.push(Literal::ascii(utils::yul_ident_name(name))?.into())
+            Constant::BitCast(i) => self._flatten(values, &i.operand)?,
+            Constant::ICmp(i) => values.push(self._build_icmp(i)?),
+            Constant::GetElementPtr(i) => values.push(self._build_gep(i)?),
+            Constant::GlobalReference { name, ty } => {
+                values.push(self._build_global_reference(name, ty)?)
             }
             _ => {
                 return Err(anyhow!(
@@ -63,6 +82,176 @@ impl<'a> ConstantFlatter<'a> {
         Ok(())
     }
 
+    fn _build_single(&self, constant: &Constant) -> Result<Value> {
+        let mut values = self.flatten(constant)?;
+
+        if values.len() != 1 {
+            return Err(anyhow!(
+                "{} single constant: {}",
+                error::WRONG_ARG,
+                constant
+            ));
+        }
+
+        Ok(values.remove(0))
+    }
+
+    /// Bits of integer constant, pointer is a word.
+    fn _int_bits(&self, constant: &Constant) -> Result<u32> {
+        match self.types.type_of(constant).as_ref() {
+            Type::IntegerType { bits } if *bits <= 256 => Ok(*bits),
+            Type::PointerType { addr_space: _ } => Ok(256),
+            ty => Err(anyhow!(
+                "{} constant expression: {ty}",
+                error::UNSUPPERTED_OPERAND
+            )),
+        }
+    }
+
+    /// Constant expressions are lowered same as instructions, then folded if operands are literal.
+    fn _build_binary(
+        &self,
+        op: BinaryOperator,
+        operand0: &Constant,
+        operand1: &Constant,
+    ) -> Result<Value> {
+        let a = self._build_single(operand0)?;
+        let b = self._build_single(operand1)?;
+
+        utils::fold_value(build_binary(
+            self.config,
+            op,
+            a,
+            b,
+            self._int_bits(operand0)?,
+        )?)
+    }
+
+    fn _build_icmp(&self, icmp: &constant::ICmp) -> Result<Value> {
+        let a = self._build_single(&icmp.operand0)?;
+        let b = self._build_single(&icmp.operand1)?;
+
+        let bits = self._int_bits(&icmp.operand0)?;
+
+        utils::fold_value(build_icmp(icmp.predicate, a, b, bits)?)
+    }
+
+    /// Source element type is kept by `utils::mark_const_geps`, which replaces address with a
+    /// marker. Without it, only zero indices are supported.
+    fn _build_gep(&self, gep: &constant::GetElementPtr) -> Result<Value> {
+        let marker = match gep.address.as_ref() {
+            Constant::GlobalReference { name, ty: _ } => {
+                self.config.gep_types.get(&utils::yul_ident_name(name))
+            }
+            _ => None,
+        };
+
+        let (ty, base) = match marker {
+            Some((ty, address)) => (ty, self._build_single(address)?),
+            None if gep
+                .indices
+                .iter()
+                .all(|i| matches!(i.as_ref(), Constant::Int { value: 0, .. })) =>
+            {
+                return self._build_single(&gep.address)
+            }
+            None => {
+                return Err(anyhow!(
+                    "{} constant getelementptr without source type, module isn't marked by \
+                     `mark_const_geps` of `compile_object_from_*`: {gep}",
+                    error::UNSUPPERTED_OPERAND
+                ))
+            }
+        };
+
+        let indices: Vec<Operand> = gep
+            .indices
+            .iter()
+            .map(|i| Operand::ConstantOperand(i.clone()))
+            .collect();
+
+        let value = build_element_address(self.types, self.config, ty, base, &indices)?;
+
+        utils::fold_value(value)
+    }
+
+    /// `[N x i8]` is packed into words of 32 bytes. Full word is written as string literal if it's
+    /// printable, or hex string literal. The last short word is a number.
+    fn _build_bytes(&self, values: &mut Vec<Value>, elements: &[ConstantRef]) -> Result<()> {
+        let mut bytes = Vec::with_capacity(elements.len());
+
+        for e in elements {
+            match e.as_ref() {
+                Constant::Int { bits: _, value } => bytes.push(*value as u8),
+                Constant::Undef(_) | Constant::Poison(_) | Constant::AggregateZero(_) => {
+                    bytes.push(0)
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "{} byte array element: {}",
+                        error::UNSUPPERTED_OPERAND,
+                        e
+                    ))
+                }
+            }
+        }
+
+        for chunk in bytes.chunks(32) {
+            let literal = if chunk.len() < 32 || chunk.iter().all(|b| *b == 0) {
+                Literal::u256_number(U256::from_big_endian(chunk))?
+            } else if chunk.iter().all(|b| is_plain_ascii(*b)) {
+                Literal::ascii(String::from_utf8_lossy(chunk))?
+            } else {
+                Literal::hex(chunk.iter().map(|b| format!("{b:02x}")).collect::<String>())?
+            };
+
+            values.push(literal.into());
+        }
+
+        Ok(())
+    }
+
+    /// Integer wider than 64 bits may be a tag of wide constant.
+    ///
+    /// Integer is unsigned value of its bit width, signed operations sign extend it by
+    /// `utils::sign_extend`.
+    fn _build_int(&self, bits: u32, value: u64) -> Result<Literal> {
+        let mut v = U256::from(value);
+
+        if bits > 64 {
+            if let Some(w) = self.config.wide_constants.get(&value) {
+                v = *w;
+            }
+        }
+
+        Literal::u256_number(v)
+    }
+
+    /// Function is referenced by its ID, mutable global by its address in reserved region, constant
+    /// global by address of data loaded into memory. Others have no address, like external global.
+    fn _build_global_reference(&self, name: &Name, ty: &Type) -> Result<Value> {
+        if let (Name::Name(n), Type::FuncType { .. }) = (name, ty) {
+            if let Some(id) = self.config.function_ids.get(n.as_str()) {
+                return Ok(Literal::int_number(*id)?.into());
+            }
+        }
+
+        let name = utils::yul_ident_name(name);
+
+        if let Some(address) = self.config.static_addresses.get(&name) {
+            return Ok(Literal::int_number(*address)?.into());
+        }
+
+        if self.config.data_globals.contains(&name) {
+            return utils::global_address(&name);
+        }
+
+        Err(anyhow!(
+            "{} global without address: {name}",
+            error::UNSUPPERTED_OPERAND
+        ))
+    }
+
     fn _build_type_values(&self, values: &mut Vec<Value>, ty: &Type) -> Result<()> {
         let flatter = TypeFlatter::new(self.types, self.config);
 
@@ -75,3 +264,72 @@ impl<'a> ConstantFlatter<'a> {
         Ok(())
     }
 }
+
+/// Operator and operands of integer binary constant expression.
+pub(crate) fn binary_constant(
+    constant: &Constant,
+) -> Option<(BinaryOperator, &Constant, &Constant)> {
+    let (op, operand0, operand1) = match constant {
+        Constant::Add(i) => (BinaryOperator::Add, &i.operand0, &i.operand1),
+        Constant::Sub(i) => (BinaryOperator::Sub, &i.operand0, &i.operand1),
+        Constant::Mul(i) => (BinaryOperator::Mul, &i.operand0, &i.operand1),
+        Constant::And(i) => (BinaryOperator::And, &i.operand0, &i.operand1),
+        Constant::Or(i) => (BinaryOperator::Or, &i.operand0, &i.operand1),
+        Constant::Xor(i) => (BinaryOperator::Xor, &i.operand0, &i.operand1),
+        Constant::Shl(i) => (BinaryOperator::Shl, &i.operand0, &i.operand1),
+        Constant::LShr(i) => (BinaryOperator::LShr, &i.operand0, &i.operand1),
+        Constant::AShr(i) => (BinaryOperator::AShr, &i.operand0, &i.operand1),
+        _ => return None,
+    };
+
+    Some((op, operand0.as_ref(), operand1.as_ref()))
+}
+
+/// Byte which can be written in string literal without escape.
+fn is_plain_ascii(b: u8) -> bool {
+    (b.is_ascii_graphic() || b == b' ') && b != b'"' && b != b'\\'
+}
+
+#[cfg(test)]
+mod tests {
+    use yuler::Writer;
+
+    use crate::{Compiler, Config, IntSemantics};
+
+    #[test]
+    fn test_int_zero_extended_in_wide() {
+        let ir = r#"
+            define i8 @f(ptr %p) {
+            entry:
+              %x = load i8, ptr %p
+              %c = icmp eq i8 %x, 200
+              %d = sdiv i8 %x, -2
+              switch i8 %x, label %other [ i8 200, label %hit ]
+            hit:
+              %r = zext i1 %c to i8
+              ret i8 %r
+            other:
+              ret i8 %d
+            }
+        "#;
+
+        let config = Config::default().int_semantics(IntSemantics::Wide);
+        let object = Compiler::new(config)
+            .compile_object_from_ir(ir, "f")
+            .unwrap();
+
+        let mut w = Writer::new(Vec::new(), "");
+        object.write(&mut w).unwrap();
+        let yul = String::from_utf8(w.w).unwrap();
+
+        assert!(yul.contains("c := eq(x, 200)"), "{yul}");
+        assert!(yul.contains("case 200 {"), "{yul}");
+        assert!(
+            yul.contains(
+                "d := and(sdiv(signextend(0, x), \
+                 0xfffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffe), 0xff)"
+            ),
+            "{yul}"
+        );
+    }
+}
@@ -1,11 +1,13 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use llvm_ir::{types::Types, Function};
-use llvm_ir_analysis::FunctionAnalysis;
 use yuler::{FunctionDefinition, Ident};
 
-use crate::{utils, BlockCompiler, Config, TypeFlatter};
+use crate::{
+    utils::{self, ControlFlowBuilder},
+    BlockCompiler, Config, PhiCompiler, TypeFlatter,
+};
 
 pub struct FunctionCompiler<'a> {
     llvm_func: &'a Function,
@@ -42,11 +44,14 @@ impl<'a> FunctionCompiler<'a> {
         Ok(())
     }
 
-    pub fn compile_function_body(self) -> Result<(FunctionDefinition, Vec<String>)> {
+    /// Compile function body, returns function, objects and helper functions it used.
+    pub fn compile_function_body(
+        self,
+    ) -> Result<(FunctionDefinition, Vec<String>, BTreeSet<String>)> {
         let mut objects = Vec::new();
+        let mut helpers = BTreeSet::new();
 
         let mut blocks = BTreeMap::new();
-        let mut llvm_blocks = BTreeMap::new();
         // Compile all blocks
         {
             for bb in &self.llvm_func.basic_blocks {
@@ -60,37 +65,45 @@ impl<'a> FunctionCompiler<'a> {
                     objects.push(obj);
                 }
 
-                let name = utils::yul_ident_name(&bb.name);
+                helpers.append(&mut block_compiler.helpers);
 
-                blocks.insert(name.clone(), block);
-                llvm_blocks.insert(name, bb);
+                blocks.insert(bb.name.clone(), block);
             }
         }
 
         // Compile termiantor
-        {
-            for (name, block) in &blocks {}
+        let mut body = {
+            let mut builder =
+                ControlFlowBuilder::new(self.llvm_func, self.llvm_types, self.config, blocks);
+
+            match builder.build() {
+                Ok(body) => body,
+                Err(e) if e.is::<utils::Unstructured>() => {
+                    log::debug!("Fallback to dispatch loop: {e}");
+
+                    builder.build_dispatch()?
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        // Variables may be used in other blocks, declare them at beginning of function.
+        if self.llvm_func.basic_blocks.len() > 1 {
+            body = utils::hoist_declares(body)?;
         }
 
         // Build function
         let func = {
-            let function_analysis = FunctionAnalysis::new(self.llvm_func);
-            let control_flow = function_analysis.control_flow_graph();
-
-            let entry = control_flow.entry();
-            let entry = utils::yul_ident_name(entry);
-
-            let mut block = blocks
-                .remove(&entry)
-                .ok_or(anyhow!("Fatel: Failed to get basic block: {entry}"))?;
+            let phi_compiler = PhiCompiler::new(self.llvm_func, self.llvm_types, self.config);
 
             let mut func = self.func;
 
-            func.block.0.append(&mut block);
+            func.block.0.append(&mut phi_compiler.compile_declares()?);
+            func.block.0.append(&mut body);
 
             func
         };
 
-        Ok((func, objects))
+        Ok((func, objects, helpers))
     }
 }
@@ -0,0 +1,93 @@
+use anyhow::{anyhow, Result};
+use llvm_ir::{module::GlobalVariable, types::Types};
+use yuler::{Data, FunctionDefinition, Value};
+
+use crate::{error, utils, Config, ConstantFlatter, TypeFlatter};
+
+/// Compile constant global into data section and its loader.
+///
+/// Initializer is serialized by memory layout. Integers are written in big endian, values which
+/// can't be known at compile time, like address of other global, become relocations.
+pub struct GlobalCompiler<'a> {
+    global: &'a GlobalVariable,
+    types: &'a Types,
+    config: &'a Config,
+}
+
+impl<'a> GlobalCompiler<'a> {
+    pub fn new(global: &'a GlobalVariable, types: &'a Types, config: &'a Config) -> Self {
+        Self {
+            global,
+            types,
+            config,
+        }
+    }
+
+    /// Data section and loader of global, which caches address in `slot`.
+    pub fn compile(&self, slot: u64) -> Result<(Option<Data>, FunctionDefinition)> {
+        let data = self.serialize()?;
+
+        let loader = utils::build_global_loader(&data, slot)?;
+
+        Ok((utils::build_data_section(&data)?, loader))
+    }
+
+    pub fn serialize(&self) -> Result<utils::GlobalData> {
+        let initializer = self.global.initializer.as_ref().ok_or(anyhow!(
+            "{} global without initializer: {}",
+            error::WRONG_ARG,
+            self.global.name
+        ))?;
+
+        let ty = self.types.type_of(initializer);
+
+        let type_flatter = TypeFlatter::new(self.types, self.config);
+        let layout = type_flatter.compute_layout(&ty)?;
+        let size = type_flatter.compute_size(&ty)?;
+
+        let constant_flatter = ConstantFlatter::new(self.types, self.config);
+        let values = constant_flatter.flatten(initializer)?;
+
+        if values.len() != layout.len() {
+            return Err(anyhow!(
+                "{} global layout: {}",
+                error::WRONG_ARG,
+                self.global.name
+            ));
+        }
+
+        let mut data = utils::GlobalData {
+            name: utils::yul_ident_name(&self.global.name),
+            bytes: vec![0; size as usize],
+            relocations: Vec::new(),
+        };
+
+        for ((offset, size), value) in layout.into_iter().zip(values) {
+            let number = match &value {
+                Value::Literal(l) => l.as_word(),
+                _ => None,
+            };
+
+            let slot = &mut data.bytes[offset as usize..(offset + size) as usize];
+
+            match (number, size) {
+                (Some(n), 1..=32) => {
+                    let mut word = [0u8; 32];
+                    n.to_big_endian(&mut word);
+
+                    slot.copy_from_slice(&word[32 - size as usize..]);
+                }
+                (None, 32) => data.relocations.push((offset, value)),
+                _ => {
+                    return Err(anyhow!(
+                        "{} global value: {}",
+                        error::UNSUPPERTED_OPERAND,
+                        self.global.name
+                    ))
+                }
+            }
+        }
+
+        Ok(data)
+    }
+}
@@ -0,0 +1,134 @@
+use anyhow::{anyhow, Result};
+use llvm_ir::{types::Types, Name, Operand, Type};
+use yuler::{Ident, Statement, Value, VariableDeclare};
+
+use crate::{error, utils, Config, OperandFlatter};
+
+/// Integer binary operator of LLVM IR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Add,
+    Sub,
+    Mul,
+    UDiv,
+    SDiv,
+    URem,
+    SRem,
+    Shl,
+    LShr,
+    AShr,
+    And,
+    Or,
+    Xor,
+}
+
+/// Lower binary operator into EVM builtin.
+///
+/// Operands are kept as unsigned value of their bit width. Signed operators sign extend
+/// operands first, and results which may be out of bit width are masked.
+///
+/// With `IntSemantics::Wide` results of `add`, `sub`, `mul` and `shl` are not masked.
+pub struct BinaryCompiler<'a> {
+    op: BinaryOperator,
+    operand0: &'a Operand,
+    operand1: &'a Operand,
+    dest: &'a Name,
+    types: &'a Types,
+    config: &'a Config,
+}
+
+impl<'a> BinaryCompiler<'a> {
+    pub fn new(
+        op: BinaryOperator,
+        operand0: &'a Operand,
+        operand1: &'a Operand,
+        dest: &'a Name,
+        types: &'a Types,
+        config: &'a Config,
+    ) -> Self {
+        Self {
+            op,
+            operand0,
+            operand1,
+            dest,
+            types,
+            config,
+        }
+    }
+
+    pub fn compile(&self) -> Result<Vec<Statement>> {
+        let bits = match self.types.type_of(self.operand0).as_ref() {
+            Type::IntegerType { bits } if *bits <= 256 => *bits,
+            ty => {
+                return Err(anyhow!(
+                    "{} binary operator: {ty}",
+                    error::UNSUPPERTED_OPERAND
+                ))
+            }
+        };
+
+        let flatter = OperandFlatter::new(self.types, self.config);
+
+        let a = flatter.flatten_single(self.operand0)?;
+        let b = flatter.flatten_single(self.operand1)?;
+
+        let value = build_binary(self.config, self.op, a, b, bits)?;
+
+        let dest = utils::yul_ident_name(self.dest);
+        let dest = Ident::new(utils::tidy_name(&dest))?;
+
+        Ok(vec![VariableDeclare {
+            names: vec![dest],
+            value,
+        }
+        .into()])
+    }
+}
+
+/// Lower binary operator on values of `bits` bits.
+pub(crate) fn build_binary(
+    config: &Config,
+    op: BinaryOperator,
+    a: Value,
+    b: Value,
+    bits: u32,
+) -> Result<Value> {
+    let wrap_bits = config.wrap_bits(bits);
+
+    let value = match op {
+        BinaryOperator::Add => utils::mask_bits(call("add", a, b)?, wrap_bits)?,
+        BinaryOperator::Sub => utils::mask_bits(call("sub", a, b)?, wrap_bits)?,
+        BinaryOperator::Mul => utils::mask_bits(call("mul", a, b)?, wrap_bits)?,
+        BinaryOperator::UDiv => call("div", a, b)?,
+        BinaryOperator::URem => call("mod", a, b)?,
+        BinaryOperator::SDiv => {
+            let a = utils::sign_extend(a, bits)?;
+            let b = utils::sign_extend(b, bits)?;
+
+            utils::mask_bits(call("sdiv", a, b)?, bits)?
+        }
+        BinaryOperator::SRem => {
+            let a = utils::sign_extend(a, bits)?;
+            let b = utils::sign_extend(b, bits)?;
+
+            utils::mask_bits(call("smod", a, b)?, bits)?
+        }
+        // Shift of EVM takes shift bits as first argument.
+        BinaryOperator::Shl => utils::mask_bits(call("shl", b, a)?, wrap_bits)?,
+        BinaryOperator::LShr => call("shr", b, a)?,
+        BinaryOperator::AShr => {
+            let a = utils::sign_extend(a, bits)?;
+
+            utils::mask_bits(call("sar", b, a)?, bits)?
+        }
+        BinaryOperator::And => call("and", a, b)?,
+        BinaryOperator::Or => call("or", a, b)?,
+        BinaryOperator::Xor => call("xor", a, b)?,
+    };
+
+    Ok(value)
+}
+
+fn call(name: &str, a: Value, b: Value) -> Result<Value> {
+    Ok(utils::builtin_call(name, vec![a, b])?.into())
+}
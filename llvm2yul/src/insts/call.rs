@@ -3,13 +3,14 @@ use llvm_ir::{instruction::Call, types::Types, Constant, Operand, Type};
 use primitive_types::U256;
 use yuler::{FunctionCall, Ident, Literal, Statement, Value, VariableDeclare};
 
-use crate::{error, utils, Config, ConstantFlatter, TypeFlatter};
+use crate::{error, utils, Config, ConstantFlatter, OperandFlatter, TypeFlatter};
 
 pub struct CallCompiler<'a> {
     call: &'a Call,
     types: &'a Types,
     config: &'a Config,
     pub(crate) object: Option<String>,
+    pub(crate) dispatcher: Option<String>,
 }
 
 impl<'a> CallCompiler<'a> {
@@ -20,19 +21,32 @@ impl<'a> CallCompiler<'a> {
             types,
 
             object: None,
+            dispatcher: None,
         }
     }
 
     pub fn compile_call(&mut self) -> Result<Statement> {
+        if let Some(callee) = self.build_indirect_callee()? {
+            return self.compile_indirect_call(callee);
+        }
+
         let (call_name, rets) = self.build_call_function_name_and_rets()?;
 
+        if call_name == "__yul__ext_literal_str" {
+            return Ok(VariableDeclare {
+                names: rets,
+                value: self.build_literal_str()?.into(),
+            }
+            .into());
+        }
+
         // build function call name
         let mut func_call = FunctionCall::new(Ident::new(&call_name)?);
 
         // build function call paramters
         func_call.args = if utils::is_builtin(&call_name) {
             // builtin functions don't have any struct parameters. It just have plain type.
-            self.build_call_function_parameters_directly()?
+            self.build_call_function_parameters_directly(&call_name)?
         } else {
             // For common function call may have struct, we can build function call parameter based
             // on map of args
@@ -62,6 +76,62 @@ impl<'a> CallCompiler<'a> {
         })
     }
 
+    /// Callee of indirect call, which is function ID. `None` if calling function directly.
+    fn build_indirect_callee(&self) -> Result<Option<Value>> {
+        let operand = self
+            .call
+            .function
+            .as_ref()
+            .right()
+            .ok_or(anyhow!("function must be operand"))?;
+
+        if let Some(Constant::GlobalReference { .. }) = operand.as_constant() {
+            return Ok(None);
+        }
+
+        let flatter = OperandFlatter::new(self.types, self.config);
+
+        Ok(Some(flatter.flatten_single(operand)?))
+    }
+
+    /// Call function by ID through dispatcher of same flattened arguments and returns.
+    fn compile_indirect_call(&mut self, callee: Value) -> Result<Statement> {
+        let result_type = match self.call.function_ty.as_ref() {
+            Type::FuncType { result_type, .. } => result_type,
+            ty => return Err(anyhow!("{} indirect call: {ty}", error::WRONG_ARG)),
+        };
+
+        let rets = match &self.call.dest {
+            Some(dest) => {
+                let flatter = TypeFlatter::new(self.types, self.config);
+                flatter.flatten_parameter(dest, result_type)?
+            }
+            None => vec![],
+        };
+
+        let mut args = self.build_call_function_parameters()?;
+
+        let name = utils::dispatcher_name(args.len(), rets.len());
+        self.dispatcher = Some(name.clone());
+
+        args.insert(0, callee);
+
+        let func_call = FunctionCall {
+            name: Ident::new(name)?,
+            args,
+        };
+
+        Ok(if rets.is_empty() {
+            func_call.into()
+        } else {
+            VariableDeclare {
+                names: rets,
+                value: func_call.into(),
+            }
+            .into()
+        })
+    }
+
     fn build_call_function_name_and_rets(&self) -> Result<(String, Vec<Ident>)> {
         let operand = self
             .call
@@ -98,12 +168,53 @@ impl<'a> CallCompiler<'a> {
         }
     }
 
+    /// Literal of number in constant global string, which is argument of `__yul__ext_literal_str`.
+    fn build_literal_str(&self) -> Result<Literal> {
+        let name = match self.call.arguments.as_slice() {
+            [(arg, _)] => match arg.as_constant().and_then(literal_str_global) {
+                Some(name) => name,
+                None => {
+                    return Err(anyhow!(
+                        "{} __yul__ext_literal_str: {arg}",
+                        error::WRONG_ARG
+                    ))
+                }
+            },
+            _ => {
+                return Err(anyhow!(
+                    "{} __yul__ext_literal_str: {}",
+                    error::WRONG_ARG,
+                    self.call
+                ))
+            }
+        };
+
+        let bytes = self.config.const_strings.get(&name).ok_or(anyhow!(
+            "{} __yul__ext_literal_str: {name} isn't a constant string",
+            error::WRONG_ARG
+        ))?;
+
+        let s = std::str::from_utf8(bytes)
+            .map_err(|_| anyhow!("Malformed number of __yul__ext_literal_str: {bytes:?}"))?;
+
+        Literal::u256_number(utils::parse_literal_str(s)?)
+    }
+
     /// Build builtin function call without type flatten
-    pub(crate) fn build_call_function_parameters_directly(&self) -> Result<Vec<Value>> {
+    pub(crate) fn build_call_function_parameters_directly(
+        &self,
+        call_name: &str,
+    ) -> Result<Vec<Value>> {
         let mut res = Vec::new();
 
+        // Only these builtins take name of object or data.
+        let by_name = matches!(
+            call_name,
+            "__yul_datasize" | "__yul_dataoffset" | "__yul_objectsize" | "__yul_objectoffset"
+        );
+
         for (arg, _) in &self.call.arguments {
-            res.push(self.build_function_arg_from_llvm_directly(arg)?);
+            res.push(self.build_function_arg_from_llvm_directly(arg, by_name)?);
         }
 
         Ok(res)
@@ -132,15 +243,21 @@ impl<'a> CallCompiler<'a> {
         Ok(res)
     }
 
-    fn build_function_arg_from_llvm_directly(&self, arg: &Operand) -> Result<Value> {
+    fn build_function_arg_from_llvm_directly(&self, arg: &Operand, by_name: bool) -> Result<Value> {
         match arg {
             Operand::LocalOperand { name, ty: _ } => {
                 Ok(Ident::new(utils::yul_ident_name(name))?.into())
             }
-            Operand::ConstantOperand(constant) => {
-                let v = self.build_function_arg_from_constant(constant)?.remove(0);
-                Ok(v)
-            }
+            // Builtin like `datasize` takes name of object.
+            Operand::ConstantOperand(constant) => match constant.as_ref() {
+                Constant::GlobalReference { name, ty: _ } if by_name => {
+                    Ok(Literal::ascii(utils::yul_ident_name(name))?.into())
+                }
+                _ => {
+                    let v = self.build_function_arg_from_constant(constant)?.remove(0);
+                    Ok(v)
+                }
+            },
             _ => Err(anyhow!(
                 "Unsupported function paramter for builtin function"
             )),
@@ -245,3 +362,19 @@ fn convert_builtin(function_call: &mut FunctionCall) -> Result<()> {
 
     Ok(())
 }
+
+/// Global which argument of `__yul__ext_literal_str` points to, a global or `getelementptr` of it
+/// with zero indices.
+fn literal_str_global(constant: &Constant) -> Option<String> {
+    match constant {
+        Constant::GlobalReference { name, ty: _ } => Some(utils::yul_ident_name(name)),
+        Constant::GetElementPtr(g)
+            if g.indices
+                .iter()
+                .all(|i| matches!(i.as_ref(), Constant::Int { value: 0, .. })) =>
+        {
+            literal_str_global(&g.address)
+        }
+        _ => None,
+    }
+}
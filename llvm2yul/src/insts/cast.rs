@@ -0,0 +1,124 @@
+use anyhow::{anyhow, Result};
+use llvm_ir::{types::Types, Name, Operand, Type};
+use yuler::{Ident, Statement, VariableDeclare};
+
+use crate::{error, utils, Config, IntSemantics, OperandFlatter};
+
+/// Integer cast operator of LLVM IR.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CastOperator {
+    Trunc,
+    ZExt,
+    SExt,
+}
+
+/// Lower integer casts.
+///
+/// Value is kept as unsigned value of its bit width, so `zext` is a copy, `trunc` masks and
+/// `sext` sign extends then masks to the new bit width.
+///
+/// With `IntSemantics::Wide` value may have bits over its bit width after `add`, `sub`, `mul` or
+/// `shl`, so `zext` masks it too.
+pub struct CastCompiler<'a> {
+    op: CastOperator,
+    operand: &'a Operand,
+    to_type: &'a Type,
+    dest: &'a Name,
+    types: &'a Types,
+    config: &'a Config,
+}
+
+impl<'a> CastCompiler<'a> {
+    pub fn new(
+        op: CastOperator,
+        operand: &'a Operand,
+        to_type: &'a Type,
+        dest: &'a Name,
+        types: &'a Types,
+        config: &'a Config,
+    ) -> Self {
+        Self {
+            op,
+            operand,
+            to_type,
+            dest,
+            types,
+            config,
+        }
+    }
+
+    pub fn compile(&self) -> Result<Vec<Statement>> {
+        let from_bits = int_bits(&self.types.type_of(self.operand))?;
+        let to_bits = int_bits(self.to_type)?;
+
+        let flatter = OperandFlatter::new(self.types, self.config);
+        let value = flatter.flatten_single(self.operand)?;
+
+        let value = match self.op {
+            CastOperator::Trunc => utils::mask_bits(value, to_bits)?,
+            CastOperator::ZExt if self.config.int_semantics == IntSemantics::Wide => {
+                utils::mask_bits(value, from_bits)?
+            }
+            CastOperator::ZExt => value,
+            CastOperator::SExt => utils::mask_bits(utils::sign_extend(value, from_bits)?, to_bits)?,
+        };
+
+        let dest = utils::yul_ident_name(self.dest);
+        let dest = Ident::new(utils::tidy_name(&dest))?;
+
+        Ok(vec![VariableDeclare {
+            names: vec![dest],
+            value,
+        }
+        .into()])
+    }
+}
+
+fn int_bits(ty: &Type) -> Result<u32> {
+    match ty {
+        Type::IntegerType { bits } if *bits <= 256 => Ok(*bits),
+        _ => Err(anyhow!("{} cast: {ty}", error::UNSUPPERTED_OPERAND)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yuler::Writer;
+
+    use crate::{Compiler, Config, IntSemantics};
+
+    #[test]
+    fn test_casts_keep_bits_in_wide() {
+        let ir = r#"
+            define i64 @f(ptr %p, i64 %a, i64 %b) {
+              %x = load i8, ptr %p
+              %s = sext i8 %x to i64
+              %w = add i64 %a, %b
+              %t = trunc i64 %w to i32
+              %z = zext i32 %t to i64
+              %y = add i64 %w, 1
+              %u = zext i64 %y to i128
+              %v = trunc i128 %u to i64
+              %r = add i64 %s, %v
+              ret i64 %r
+            }
+        "#;
+
+        let config = Config::default().int_semantics(IntSemantics::Wide);
+        let object = Compiler::new(config)
+            .compile_object_from_ir(ir, "f")
+            .unwrap();
+
+        let mut w = Writer::new(Vec::new(), "");
+        object.write(&mut w).unwrap();
+        let yul = String::from_utf8(w.w).unwrap();
+
+        assert!(
+            yul.contains("let s := and(signextend(0, x), 0xffffffffffffffff)"),
+            "{yul}"
+        );
+        assert!(yul.contains("let t := and(w, 0xffffffff)"), "{yul}");
+        assert!(yul.contains("let z := and(t, 0xffffffff)"), "{yul}");
+        assert!(yul.contains("let u := and(y, 0xffffffffffffffff)"), "{yul}");
+    }
+}
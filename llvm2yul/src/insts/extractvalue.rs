@@ -1,16 +1,23 @@
 use anyhow::{anyhow, Result};
-use llvm_ir::{instruction::ExtractValue, Name, Operand, Type};
-use yuler::{Ident, Statement, VariableDeclare};
+use llvm_ir::{instruction::ExtractValue, types::Types, Name, Operand, Type};
+use yuler::{Ident, Literal, Statement, Value, VariableDeclare};
 
-use crate::{error, utils};
+use crate::{byte_array_len, error, utils, Config, TypeFlatter};
 
+/// Element of byte array is taken from its packed word by `byte`.
 pub struct ExtractValueCompiler<'a> {
     inst: &'a ExtractValue,
+    types: &'a Types,
+    config: &'a Config,
 }
 
 impl<'a> ExtractValueCompiler<'a> {
-    pub fn new(inst: &'a ExtractValue) -> Self {
-        Self { inst }
+    pub fn new(inst: &'a ExtractValue, types: &'a Types, config: &'a Config) -> Self {
+        Self {
+            inst,
+            types,
+            config,
+        }
     }
 
     pub fn compile(&self) -> Result<Vec<Statement>> {
@@ -18,20 +25,63 @@ impl<'a> ExtractValueCompiler<'a> {
 
         let mut res = Vec::new();
 
-        let from = build_from_name(&name, &self.inst.indices)?;
+        let from = self.build_from(&name)?;
         let to = utils::yul_ident_name(&self.inst.dest);
         let to = Ident::new(utils::tidy_name(&to))?;
 
         res.push(
             VariableDeclare {
                 names: vec![to],
-                value: from.into(),
+                value: from,
             }
             .into(),
         );
 
         Ok(res)
     }
+
+    fn build_from(&self, name: &Name) -> Result<Value> {
+        let type_flatter = TypeFlatter::new(self.types, self.config);
+
+        let mut ty = self.types.type_of(&self.inst.aggregate);
+
+        for (i, index) in self.inst.indices.iter().enumerate() {
+            let index = *index as usize;
+
+            if let Some(len) = byte_array_len(&ty) {
+                if i + 1 != self.inst.indices.len() || index >= len {
+                    return Err(anyhow!("{} extractvalue: {}", error::WRONG_ARG, self.inst));
+                }
+
+                let word = index / 32;
+                let word_len = (len - 32 * word).min(32);
+
+                let mut indices = self.inst.indices[..i].to_vec();
+                indices.push(word as u32);
+
+                let word = build_from_name(name, &indices)?.into();
+
+                return extract_byte(word, word_len, index % 32);
+            }
+
+            let element_types = type_flatter.element_types(&ty)?;
+
+            ty = element_types.get(index).cloned().ok_or(anyhow!(
+                "{} extractvalue: {}",
+                error::WRONG_ARG,
+                self.inst
+            ))?;
+        }
+
+        Ok(build_from_name(name, &self.inst.indices)?.into())
+    }
+}
+
+/// Byte at `index` of a packed word of `len` bytes, which is right aligned.
+pub(crate) fn extract_byte(word: Value, len: usize, index: usize) -> Result<Value> {
+    let position = Literal::int_number((32 - len + index) as u64)?;
+
+    Ok(utils::builtin_call("byte", vec![position.into(), word])?.into())
 }
 
 pub(crate) fn build_from_name(name: &Name, indices: &[u32]) -> Result<Ident> {
@@ -61,3 +111,67 @@ fn check_arg(ty: &Operand) -> Result<Name> {
         _ => Err(anyhow!("{} extractvalue: {}", error::WRONG_ARG, ty)),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use llvm_ir::{types::Types, Constant, ConstantRef};
+    use yuler::{Literal, Value};
+
+    use super::extract_byte;
+    use crate::{insert_byte, utils, Config, ConstantFlatter};
+
+    fn number(value: &Value) -> u64 {
+        value.as_literal().and_then(|l| l.as_number()).unwrap()
+    }
+
+    #[test]
+    fn test_byte_array_round_trip() {
+        let types = Types::blank_for_testing();
+        let config = Config::default();
+
+        let bytes: Vec<u8> = (0..33).map(|i| b'a' + i % 26).collect();
+        let constant = Constant::Array {
+            element_type: types.i8(),
+            elements: bytes
+                .iter()
+                .map(|b| {
+                    ConstantRef::new(Constant::Int {
+                        bits: 8,
+                        value: u64::from(*b),
+                    })
+                })
+                .collect(),
+        };
+
+        let words: Vec<Value> = ConstantFlatter::new(&types, &config)
+            .flatten(&constant)
+            .unwrap()
+            .iter()
+            .map(|w| {
+                let word = w.as_literal().and_then(|l| l.as_word()).unwrap();
+                Literal::u256_number(word).unwrap().into()
+            })
+            .collect();
+
+        assert_eq!(words.len(), 2);
+
+        // Index in array, then word of it, length and index in the word.
+        for (index, word, len, i) in [(5, 0, 32, 5), (31, 0, 32, 31), (32, 1, 1, 0)] {
+            let word = &words[word];
+
+            let byte = utils::fold_value(extract_byte(word.clone(), len, i).unwrap()).unwrap();
+            assert_eq!(number(&byte), u64::from(bytes[index]));
+
+            let new = Literal::int_number(0x1ee).unwrap().into();
+            let inserted = insert_byte(word.clone(), new, len, i).unwrap();
+            let inserted = utils::fold_value(inserted).unwrap();
+
+            for j in 0..len {
+                let byte = utils::fold_value(extract_byte(inserted.clone(), len, j).unwrap());
+                let expected = if j == i { 0xee } else { bytes[index - i + j] };
+
+                assert_eq!(number(&byte.unwrap()), u64::from(expected));
+            }
+        }
+    }
+}
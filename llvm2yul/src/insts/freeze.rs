@@ -0,0 +1,48 @@
+use anyhow::{anyhow, Result};
+use llvm_ir::{instruction::Freeze, types::Types};
+use yuler::{Statement, VariableDeclare};
+
+use crate::{error, Config, OperandFlatter, TypeFlatter};
+
+/// `freeze` is a copy, since poison and undef are already `0`.
+pub struct FreezeCompiler<'a> {
+    inst: &'a Freeze,
+    types: &'a Types,
+    config: &'a Config,
+}
+
+impl<'a> FreezeCompiler<'a> {
+    pub fn new(inst: &'a Freeze, types: &'a Types, config: &'a Config) -> Self {
+        Self {
+            inst,
+            types,
+            config,
+        }
+    }
+
+    pub fn compile(&self) -> Result<Vec<Statement>> {
+        let flatter = OperandFlatter::new(self.types, self.config);
+        let values = flatter.flatten(&self.inst.operand)?;
+
+        let ty = self.types.type_of(&self.inst.operand);
+
+        let flatter = TypeFlatter::new(self.types, self.config);
+        let names = flatter.flatten_parameter(&self.inst.dest, &ty)?;
+
+        if names.len() != values.len() {
+            return Err(anyhow!("{} freeze: {}", error::WRONG_ARG, self.inst));
+        }
+
+        Ok(names
+            .into_iter()
+            .zip(values)
+            .map(|(name, value)| {
+                VariableDeclare {
+                    names: vec![name],
+                    value,
+                }
+                .into()
+            })
+            .collect())
+    }
+}
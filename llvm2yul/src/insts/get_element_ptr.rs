@@ -1,19 +1,262 @@
-use anyhow::Result;
-use llvm_ir::instruction::GetElementPtr;
-use yuler::Statement;
+use anyhow::{anyhow, Result};
+use llvm_ir::{
+    instruction::GetElementPtr,
+    types::{NamedStructDef, Types},
+    Constant, Operand, Type, TypeRef,
+};
+use yuler::{Ident, Literal, Statement, Value, VariableDeclare};
 
+use crate::{error, utils, Config, OperandFlatter, TypeFlatter};
+
+/// Compute address by offset of indices, using layout of `TypeFlatter::compute_size`.
+///
+/// Constant indices are folded into a literal, dynamic indices become `mul` and `add`.
 pub struct GetElementPtrCompiler<'a> {
     inst: &'a GetElementPtr,
+    types: &'a Types,
+    config: &'a Config,
 }
 
 impl<'a> GetElementPtrCompiler<'a> {
-    pub fn new(inst: &'a GetElementPtr) -> Self {
-        Self { inst }
+    pub fn new(inst: &'a GetElementPtr, types: &'a Types, config: &'a Config) -> Self {
+        Self {
+            inst,
+            types,
+            config,
+        }
     }
 
     pub fn compile(&self) -> Result<Vec<Statement>> {
-        println!("{:#?}", self.inst);
+        let operand_flatter = OperandFlatter::new(self.types, self.config);
+
+        let base = operand_flatter.flatten_single(&self.inst.address)?;
+
+        let value = build_element_address(
+            self.types,
+            self.config,
+            &self.inst.source_element_type,
+            base,
+            &self.inst.indices,
+        )?;
+
+        let dest = utils::yul_ident_name(&self.inst.dest);
+        let dest = Ident::new(utils::tidy_name(&dest))?;
+
+        Ok(vec![VariableDeclare {
+            names: vec![dest],
+            value,
+        }
+        .into()])
+    }
+}
+
+/// Address of element at `indices` from `base`, which points to `source_element_type`.
+pub(crate) fn build_element_address(
+    types: &Types,
+    config: &Config,
+    source_element_type: &TypeRef,
+    base: Value,
+    indices: &[Operand],
+) -> Result<Value> {
+    let type_flatter = TypeFlatter::new(types, config);
+    let operand_flatter = OperandFlatter::new(types, config);
+
+    let mut offset: i128 = 0;
+    let mut terms: Vec<Value> = Vec::new();
+
+    let mut ty = source_element_type.clone();
+
+    for (i, index) in indices.iter().enumerate() {
+        let size = if i == 0 {
+            type_flatter.compute_size(&ty)?
+        } else {
+            match ty.as_ref() {
+                Type::ArrayType { element_type, .. } => {
+                    let size = type_flatter.compute_size(element_type)?;
+                    ty = element_type.clone();
+                    size
+                }
+                Type::StructType { element_types, .. } => {
+                    let (field_offset, field_ty) =
+                        build_field(&type_flatter, element_types, index)?;
+
+                    offset += field_offset as i128;
+                    ty = field_ty;
+                    continue;
+                }
+                Type::NamedStructType { name } if !config.basic_types.contains(name) => {
+                    let element_types = match types.named_struct_def(name) {
+                        Some(NamedStructDef::Defined(ty)) => match ty.as_ref() {
+                            Type::StructType { element_types, .. } => element_types,
+                            _ => return Err(anyhow!("Linked error, wrong named struct type.")),
+                        },
+                        _ => return Err(anyhow!("Linked error, no opaque supported")),
+                    };
+
+                    let (field_offset, field_ty) =
+                        build_field(&type_flatter, element_types, index)?;
+
+                    offset += field_offset as i128;
+                    ty = field_ty;
+                    continue;
+                }
+                _ => {
+                    return Err(anyhow!(
+                        "{} getelementptr: {}",
+                        error::UNSUPPERTED_OPERAND,
+                        ty
+                    ))
+                }
+            }
+        };
+
+        if let Some(index) = constant_index(index) {
+            offset += index * size as i128;
+        } else if size != 0 {
+            let index = build_dynamic_index(types, &operand_flatter, index)?;
+
+            if size == 1 {
+                terms.push(index);
+            } else {
+                let size = Literal::int_number(size)?.into();
+                terms.push(utils::builtin_call("mul", vec![index, size])?.into());
+            }
+        }
+    }
+
+    build_offset(base, terms, offset)
+}
+
+fn build_field(
+    type_flatter: &TypeFlatter,
+    element_types: &[TypeRef],
+    index: &Operand,
+) -> Result<(u64, TypeRef)> {
+    let index = constant_index(index)
+        .and_then(|i| usize::try_from(i).ok())
+        .filter(|i| *i < element_types.len())
+        .ok_or(anyhow!(
+            "{} getelementptr struct index: {}",
+            error::WRONG_ARG,
+            index
+        ))?;
+
+    let mut offset = 0;
+
+    for ty in &element_types[..index] {
+        offset += type_flatter.compute_size(ty)?;
+    }
+
+    Ok((offset, element_types[index].clone()))
+}
+
+/// Dynamic index is signed, so index narrower than a word is sign extended before scaling.
+fn build_dynamic_index(
+    types: &Types,
+    operand_flatter: &OperandFlatter,
+    index: &Operand,
+) -> Result<Value> {
+    let value = operand_flatter.flatten_single(index)?;
+
+    match types.type_of(index).as_ref() {
+        Type::IntegerType { bits } => utils::sign_extend(value, *bits),
+        _ => Err(anyhow!(
+            "{} getelementptr index: {}",
+            error::WRONG_ARG,
+            index
+        )),
+    }
+}
+
+/// Signed value of constant index.
+fn constant_index(index: &Operand) -> Option<i128> {
+    if let Operand::ConstantOperand(c) = index {
+        if let Constant::Int { bits, value } = c.as_ref() {
+            let value = if *bits < 64 && *bits > 0 {
+                let shift = 64 - bits;
+                ((*value << shift) as i64 >> shift) as i128
+            } else if *bits == 64 {
+                *value as i64 as i128
+            } else {
+                *value as i128
+            };
+
+            return Some(value);
+        }
+    }
+
+    None
+}
+
+fn build_offset(base: Value, terms: Vec<Value>, offset: i128) -> Result<Value> {
+    let base = match base.as_literal().and_then(|l| l.as_number()) {
+        // Null or constant address, fold with constant offset.
+        Some(b) if terms.is_empty() => {
+            let address = u64::try_from(b as i128 + offset)
+                .map_err(|_| anyhow!("{} getelementptr offset: {offset}", error::WRONG_ARG))?;
+
+            return Ok(Literal::int_number(address)?.into());
+        }
+        Some(0) => None,
+        _ => Some(base),
+    };
+
+    let mut value = base;
+
+    for term in terms {
+        value = Some(match value {
+            Some(v) => utils::builtin_call("add", vec![v, term])?.into(),
+            None => term,
+        });
+    }
+
+    let value = match value {
+        Some(v) if offset > 0 => {
+            let offset = Literal::int_number(offset as u64)?.into();
+            utils::builtin_call("add", vec![v, offset])?.into()
+        }
+        Some(v) if offset < 0 => {
+            let offset = Literal::int_number(offset.unsigned_abs() as u64)?.into();
+            utils::builtin_call("sub", vec![v, offset])?.into()
+        }
+        Some(v) => v,
+        None => Literal::int_number(offset as u64)?.into(),
+    };
+
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use llvm_ir::{types::Types, Name, Operand};
+    use yuler::{Ident, Value, Writer};
+
+    use super::build_element_address;
+    use crate::Config;
+
+    fn write(value: Value) -> String {
+        let mut w = Writer::new(Vec::new(), "");
+        value.write(&mut w).unwrap();
+        String::from_utf8(w.w).unwrap()
+    }
+
+    #[test]
+    fn test_negative_dynamic_index() {
+        let types = Types::blank_for_testing();
+        let config = Config::default();
+
+        let index = Operand::LocalOperand {
+            name: Name::from("i"),
+            ty: types.i32(),
+        };
+        let address = |ty| {
+            let base = Ident::new("p").unwrap().into();
+            let value =
+                build_element_address(&types, &config, &ty, base, std::slice::from_ref(&index));
+            write(value.unwrap())
+        };
 
-        Ok(vec![])
+        assert_eq!(address(types.i64()), "add(p, mul(signextend(3, i), 32))");
+        assert_eq!(address(types.i8()), "add(p, signextend(3, i))");
     }
 }
@@ -0,0 +1,82 @@
+use anyhow::{anyhow, Result};
+use llvm_ir::{instruction::ICmp, types::Types, IntPredicate, Type};
+use yuler::{Ident, Statement, Value, VariableDeclare};
+
+use crate::{error, utils, Config, OperandFlatter};
+
+/// Lower `icmp` into `eq`, `lt`, `gt`, `slt` and `sgt`, negated by `iszero`.
+///
+/// Operands of signed predicates are sign extended when bit width is less than 256.
+pub struct ICmpCompiler<'a> {
+    inst: &'a ICmp,
+    types: &'a Types,
+    config: &'a Config,
+}
+
+impl<'a> ICmpCompiler<'a> {
+    pub fn new(inst: &'a ICmp, types: &'a Types, config: &'a Config) -> Self {
+        Self {
+            inst,
+            types,
+            config,
+        }
+    }
+
+    pub fn compile(&self) -> Result<Vec<Statement>> {
+        let bits = match self.types.type_of(&self.inst.operand0).as_ref() {
+            Type::IntegerType { bits } if *bits <= 256 => *bits,
+            Type::PointerType { addr_space: _ } => 256,
+            ty => return Err(anyhow!("{} icmp: {ty}", error::UNSUPPERTED_OPERAND)),
+        };
+
+        let flatter = OperandFlatter::new(self.types, self.config);
+
+        let a = flatter.flatten_single(&self.inst.operand0)?;
+        let b = flatter.flatten_single(&self.inst.operand1)?;
+
+        let value = build_icmp(self.inst.predicate, a, b, bits)?;
+
+        let dest = utils::yul_ident_name(&self.inst.dest);
+        let dest = Ident::new(utils::tidy_name(&dest))?;
+
+        Ok(vec![VariableDeclare {
+            names: vec![dest],
+            value,
+        }
+        .into()])
+    }
+}
+
+/// Compare values of `bits` bits by predicate, result is `0` or `1`.
+pub(crate) fn build_icmp(
+    predicate: IntPredicate,
+    mut a: Value,
+    mut b: Value,
+    bits: u32,
+) -> Result<Value> {
+    let (name, negated) = match predicate {
+        IntPredicate::EQ => ("eq", false),
+        IntPredicate::NE => ("eq", true),
+        IntPredicate::UGT => ("gt", false),
+        IntPredicate::UGE => ("lt", true),
+        IntPredicate::ULT => ("lt", false),
+        IntPredicate::ULE => ("gt", true),
+        IntPredicate::SGT => ("sgt", false),
+        IntPredicate::SGE => ("slt", true),
+        IntPredicate::SLT => ("slt", false),
+        IntPredicate::SLE => ("sgt", true),
+    };
+
+    if name.starts_with('s') {
+        a = utils::sign_extend(a, bits)?;
+        b = utils::sign_extend(b, bits)?;
+    }
+
+    let mut value: Value = utils::builtin_call(name, vec![a, b])?.into();
+
+    if negated {
+        value = utils::builtin_call("iszero", vec![value])?.into();
+    }
+
+    Ok(value)
+}
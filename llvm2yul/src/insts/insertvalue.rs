@@ -1,17 +1,143 @@
-use anyhow::Result;
-use llvm_ir::instruction::InsertValue;
-use yuler::Statement;
+use anyhow::{anyhow, Result};
+use llvm_ir::{instruction::InsertValue, types::Types, TypeRef};
+use yuler::{Literal, Statement, Value, VariableDeclare};
 
+use crate::{byte_array_len, error, utils, Config, OperandFlatter, TypeFlatter};
+
+/// Copy every flattened value of aggregate into dest, values at indices are replaced by element.
+///
+/// Element of byte array replaces its byte in the packed word.
 pub struct InsertValueCompiler<'a> {
     inst: &'a InsertValue,
+    types: &'a Types,
+    config: &'a Config,
 }
 
 impl<'a> InsertValueCompiler<'a> {
-    pub fn new(inst: &'a InsertValue) -> Self {
-        Self { inst }
+    pub fn new(inst: &'a InsertValue, types: &'a Types, config: &'a Config) -> Self {
+        Self {
+            inst,
+            types,
+            config,
+        }
     }
 
     pub fn compile(&self) -> Result<Vec<Statement>> {
-        Ok(vec![])
+        let type_flatter = TypeFlatter::new(self.types, self.config);
+        let operand_flatter = OperandFlatter::new(self.types, self.config);
+
+        let ty = self.types.type_of(&self.inst.aggregate);
+
+        let names = type_flatter.flatten_parameter(&self.inst.dest, &ty)?;
+        let mut values = operand_flatter.flatten(&self.inst.aggregate)?;
+        let mut elements = operand_flatter.flatten(&self.inst.element)?;
+
+        if names.len() != values.len() {
+            return Err(anyhow!("{} insertvalue: {}", error::WRONG_ARG, self.inst));
+        }
+
+        let (start, byte) = self.compute_start(&type_flatter, ty)?;
+
+        if let Some((len, index)) = byte {
+            if elements.len() != 1 || start >= values.len() {
+                return Err(anyhow!("{} insertvalue: {}", error::WRONG_ARG, self.inst));
+            }
+
+            elements = vec![insert_byte(
+                values[start].clone(),
+                elements.remove(0),
+                len,
+                index,
+            )?];
+        }
+
+        if start + elements.len() > values.len() {
+            return Err(anyhow!("{} insertvalue: {}", error::WRONG_ARG, self.inst));
+        }
+
+        values.splice(start..start + elements.len(), elements);
+
+        let res = names
+            .into_iter()
+            .zip(values)
+            .map(|(name, value)| {
+                VariableDeclare {
+                    names: vec![name],
+                    value,
+                }
+                .into()
+            })
+            .collect();
+
+        Ok(res)
+    }
+
+    /// Position of first flattened value at indices.
+    ///
+    /// If the last index is into a byte array, position is the word containing the byte, and
+    /// length of the word and index of the byte in it are returned too.
+    fn compute_start(
+        &self,
+        type_flatter: &TypeFlatter,
+        ty: TypeRef,
+    ) -> Result<(usize, Option<(usize, usize)>)> {
+        let mut ty = ty;
+        let mut start = 0;
+
+        for (i, index) in self.inst.indices.iter().enumerate() {
+            let index = *index as usize;
+
+            if let Some(len) = byte_array_len(&ty) {
+                if i + 1 != self.inst.indices.len() || index >= len {
+                    return Err(anyhow!("{} insertvalue: {}", error::WRONG_ARG, self.inst));
+                }
+
+                let word = index / 32;
+
+                return Ok((start + word, Some(((len - 32 * word).min(32), index % 32))));
+            }
+
+            let element_types = type_flatter.element_types(&ty)?;
+
+            if index >= element_types.len() {
+                return Err(anyhow!("{} insertvalue: {}", error::WRONG_ARG, self.inst));
+            }
+
+            for e in &element_types[..index] {
+                start += type_flatter.flatten_return_type(e)?.len();
+            }
+
+            ty = element_types[index].clone();
+        }
+
+        Ok((start, None))
     }
 }
+
+/// Replace byte at `index` of a packed word of `len` bytes.
+///
+/// ```text
+/// or(and(word, not(shl(s, 0xff))), shl(s, and(byte, 0xff)))
+/// ```
+pub(crate) fn insert_byte(word: Value, byte: Value, len: usize, index: usize) -> Result<Value> {
+    let shift: Value = Literal::int_number((8 * (len - 1 - index)) as u64)?.into();
+    let mask: Value = Literal::hex_number("0xff")?.into();
+
+    let hole = utils::builtin_call(
+        "not",
+        vec![utils::builtin_call("shl", vec![shift.clone(), mask.clone()])?.into()],
+    )?;
+    let byte = utils::builtin_call(
+        "shl",
+        vec![shift, utils::builtin_call("and", vec![byte, mask])?.into()],
+    )?;
+
+    Ok(utils::builtin_call(
+        "or",
+        vec![
+            utils::builtin_call("and", vec![word, hole.into()])?.into(),
+            byte.into(),
+        ],
+    )?
+    .into())
+}
@@ -0,0 +1,627 @@
+use std::collections::BTreeSet;
+
+use anyhow::{anyhow, Result};
+use llvm_ir::{instruction::Call, types::Types, Constant, Name, Type};
+use yuler::{FunctionCall, Ident, Literal, Statement, Value, VariableDeclare};
+
+use crate::{error, utils, Config, DataLocation, IntSemantics, OperandFlatter, TypeFlatter};
+
+/// Max length of constant `memcpy` and `memset` which are unrolled.
+const MAX_UNROLL_BYTES: u64 = 8 * 32;
+
+/// Lower call of LLVM intrinsic function, like `llvm.memcpy.p0.p0.i64`.
+pub struct IntrinsicCompiler<'a> {
+    call: &'a Call,
+    types: &'a Types,
+    config: &'a Config,
+    pub(crate) helpers: BTreeSet<String>,
+}
+
+impl<'a> IntrinsicCompiler<'a> {
+    pub fn new(call: &'a Call, types: &'a Types, config: &'a Config) -> Self {
+        Self {
+            call,
+            types,
+            config,
+
+            helpers: Default::default(),
+        }
+    }
+
+    pub fn compile(&mut self) -> Result<Vec<Statement>> {
+        let name = intrinsic_name(self.call).ok_or(anyhow!(
+            "{} intrinsic: {}",
+            error::WRONG_ARG,
+            self.call
+        ))?;
+
+        let name = name.strip_prefix("llvm.").unwrap_or(name);
+
+        match name {
+            // Hints for optimizer and debugger don't generate code.
+            n if n.starts_with("lifetime.") || n.starts_with("dbg.") => Ok(vec![]),
+            "assume" | "donothing" | "sideeffect" => Ok(vec![]),
+            n if n.starts_with("expect.") => self.declare_results(self.args(1)?),
+            "trap" => Ok(vec![utils::builtin_call("invalid", vec![])?.into()]),
+            n if n.starts_with("memcpy.") => self.compile_memcpy(false),
+            n if n.starts_with("memmove.") => self.compile_memcpy(true),
+            n if n.starts_with("memset.") => self.compile_memset(),
+            n if n.starts_with("ctpop.") => self.compile_bit_helper(utils::CTPOP_HELPER, 1),
+            n if n.starts_with("ctlz.") => self.compile_bit_helper(utils::CTLZ_HELPER, 1),
+            n if n.starts_with("cttz.") => self.compile_bit_helper(utils::CTTZ_HELPER, 1),
+            n if n.starts_with("bswap.") => self.compile_bit_helper(utils::BSWAP_HELPER, 1),
+            n if n.starts_with("bitreverse.") => {
+                self.compile_bit_helper(utils::BITREVERSE_HELPER, 1)
+            }
+            n if n.starts_with("fshl.") => self.compile_bit_helper(utils::FSHL_HELPER, 3),
+            n if n.starts_with("fshr.") => self.compile_bit_helper(utils::FSHR_HELPER, 3),
+            n if n.starts_with("umin.") => self.compile_min_max("lt", false),
+            n if n.starts_with("umax.") => self.compile_min_max("gt", false),
+            n if n.starts_with("smin.") => self.compile_min_max("slt", true),
+            n if n.starts_with("smax.") => self.compile_min_max("sgt", true),
+            n if n.starts_with("abs.") => self.compile_abs(),
+            n if n.starts_with("uadd.sat.") => self.compile_saturating("add", false),
+            n if n.starts_with("usub.sat.") => self.compile_saturating("sub", false),
+            n if n.starts_with("sadd.sat.") => self.compile_saturating("add", true),
+            n if n.starts_with("ssub.sat.") => self.compile_saturating("sub", true),
+            n if n.starts_with("uadd.with.overflow.") => self.compile_overflow("add", false),
+            n if n.starts_with("usub.with.overflow.") => self.compile_overflow("sub", false),
+            n if n.starts_with("umul.with.overflow.") => self.compile_overflow("mul", false),
+            n if n.starts_with("sadd.with.overflow.") => self.compile_overflow("add", true),
+            n if n.starts_with("ssub.with.overflow.") => self.compile_overflow("sub", true),
+            n if n.starts_with("smul.with.overflow.") => self.compile_overflow("mul", true),
+            _ => Err(anyhow!("Unsupported intrinsic: {}", self.call)),
+        }
+    }
+
+    fn args(&self, num: usize) -> Result<Vec<Value>> {
+        let flatter = OperandFlatter::new(self.types, self.config);
+
+        if self.call.arguments.len() < num {
+            return Err(anyhow!("{} intrinsic: {}", error::WRONG_ARG, self.call));
+        }
+
+        self.call.arguments[..num]
+            .iter()
+            .map(|(arg, _)| flatter.flatten_single(arg))
+            .collect()
+    }
+
+    /// Integer arguments of `bits` bits. With `IntSemantics::Wide` they may have bits over bit
+    /// width, which are masked.
+    fn int_args(&self, num: usize, bits: u32) -> Result<Vec<Value>> {
+        let args = self.args(num)?;
+
+        if self.config.int_semantics != IntSemantics::Wide {
+            return Ok(args);
+        }
+
+        args.into_iter()
+            .map(|a| utils::mask_bits(a, bits))
+            .collect()
+    }
+
+    fn call_helper(&mut self, name: &str, args: Vec<Value>) -> Result<Vec<Statement>> {
+        self.helpers.insert(name.into());
+
+        Ok(vec![FunctionCall {
+            name: Ident::new(name)?,
+            args,
+        }
+        .into()])
+    }
+
+    /// `memcpy` and `memmove` use `mcopy` if supported. Otherwise small constant `memcpy` is
+    /// unrolled, others call helper function.
+    ///
+    /// Copy from calldata or code into memory uses `calldatacopy` or `codecopy`.
+    fn compile_memcpy(&mut self, overlap: bool) -> Result<Vec<Statement>> {
+        let args = self.args(3)?;
+
+        match (self.arg_location(0)?, self.arg_location(1)?) {
+            (DataLocation::Memory, DataLocation::Memory) => {}
+            (DataLocation::Memory, DataLocation::Calldata) => {
+                return Ok(vec![utils::builtin_call("calldatacopy", args)?.into()])
+            }
+            (DataLocation::Memory, DataLocation::Code) => {
+                return Ok(vec![utils::builtin_call("codecopy", args)?.into()])
+            }
+            (dst, src) => {
+                return Err(anyhow!(
+                    "{} copy from {src:?} to {dst:?}",
+                    error::UNSUPPERTED_OPERAND
+                ))
+            }
+        }
+
+        if self.config.evm_version.has_mcopy() {
+            return Ok(vec![utils::builtin_call("mcopy", args)?.into()]);
+        }
+
+        let len = args[2].as_literal().and_then(|l| l.as_number());
+
+        match len {
+            Some(len) if !overlap && len <= MAX_UNROLL_BYTES => {
+                let mut res = Vec::new();
+
+                for offset in (0..len).step_by(32) {
+                    let src = utils::offset_address(&args[1], offset)?;
+                    let word = utils::builtin_call("mload", vec![src])?;
+
+                    res.push(utils::store_bytes(
+                        utils::offset_address(&args[0], offset)?,
+                        word.into(),
+                        len - offset,
+                    )?);
+                }
+
+                Ok(res)
+            }
+            _ if overlap => self.call_helper(utils::MEMMOVE_HELPER, args),
+            _ => self.call_helper(utils::MEMCPY_HELPER, args),
+        }
+    }
+
+    fn compile_memset(&mut self) -> Result<Vec<Statement>> {
+        let args = self.args(3)?;
+
+        let location = self.arg_location(0)?;
+        if location != DataLocation::Memory {
+            return Err(anyhow!(
+                "{} memset of {location:?}",
+                error::UNSUPPERTED_OPERAND
+            ));
+        }
+
+        let len = args[2].as_literal().and_then(|l| l.as_number());
+
+        match len {
+            Some(len) if len <= MAX_UNROLL_BYTES => {
+                let word = utils::splat_byte(args[1].clone())?;
+
+                let mut res = Vec::new();
+
+                for offset in (0..len).step_by(32) {
+                    res.push(utils::store_bytes(
+                        utils::offset_address(&args[0], offset)?,
+                        word.clone(),
+                        len - offset,
+                    )?);
+                }
+
+                Ok(res)
+            }
+            _ => self.call_helper(utils::MEMSET_HELPER, args),
+        }
+    }
+
+    /// Call bit manipulation helper, bit width of LLVM type is passed as last argument.
+    fn compile_bit_helper(&mut self, helper: &str, num_args: usize) -> Result<Vec<Statement>> {
+        let bits = self.arg_bits(0)?;
+
+        let mut args = self.int_args(num_args, bits)?;
+        args.push(Literal::int_number(u64::from(bits))?.into());
+
+        self.helpers.insert(helper.into());
+
+        let value = FunctionCall {
+            name: Ident::new(helper)?,
+            args,
+        };
+
+        self.declare_results(vec![value.into()])
+    }
+
+    /// Result and overflow flag of `{add, sub, mul}.with.overflow`, at bit width of LLVM type.
+    fn compile_overflow(&self, op: &str, signed: bool) -> Result<Vec<Statement>> {
+        let bits = self.arg_bits(0)?;
+
+        let mut args = self.int_args(2, bits)?;
+        let b = args.remove(1);
+        let a = args.remove(0);
+
+        let (result, overflow) = if signed {
+            build_signed_overflow(op, a, b, bits)?
+        } else {
+            build_unsigned_overflow(op, a, b, bits)?
+        };
+
+        self.declare_results(vec![result, overflow])
+    }
+
+    /// `min` and `max` select the operand which `cmp` holds for.
+    fn compile_min_max(&self, cmp: &str, signed: bool) -> Result<Vec<Statement>> {
+        let bits = self.arg_bits(0)?;
+
+        let mut args = self.int_args(2, bits)?;
+        let b = args.remove(1);
+        let a = args.remove(0);
+
+        let cond = if signed {
+            call(
+                cmp,
+                vec![
+                    utils::sign_extend(a.clone(), bits)?,
+                    utils::sign_extend(b.clone(), bits)?,
+                ],
+            )?
+        } else {
+            call(cmp, vec![a.clone(), b.clone()])?
+        };
+
+        self.declare_results(vec![select(cond, a, b)?])
+    }
+
+    /// `abs` of minimum signed value is itself.
+    fn compile_abs(&self) -> Result<Vec<Statement>> {
+        let bits = self.arg_bits(0)?;
+
+        let a = self.int_args(1, bits)?.remove(0);
+
+        let negative = call("slt", vec![utils::sign_extend(a.clone(), bits)?, num(0)?])?;
+        let negated = utils::mask_bits(call("sub", vec![num(0)?, a.clone()])?, bits)?;
+
+        self.declare_results(vec![select(negative, negated, a)?])
+    }
+
+    /// Saturating `add` and `sub` clamp wrapped result to bound when it overflows.
+    fn compile_saturating(&self, op: &str, signed: bool) -> Result<Vec<Statement>> {
+        let bits = self.arg_bits(0)?;
+
+        let mut args = self.int_args(2, bits)?;
+        let b = args.remove(1);
+        let a = args.remove(0);
+
+        let value = if signed {
+            // Overflowed result has the sign of `a`, so it is clamped to max, or min of
+            // `max + 1` when `a` is negative.
+            let negative = call("slt", vec![utils::sign_extend(a.clone(), bits)?, num(0)?])?;
+            let bound = call("add", vec![signed_max(bits)?, negative])?;
+
+            let (result, overflow) = build_signed_overflow(op, a, b, bits)?;
+
+            select(overflow, bound, result)?
+        } else {
+            let (result, overflow) = build_unsigned_overflow(op, a, b, bits)?;
+
+            match op {
+                "add" => call(
+                    "or",
+                    vec![
+                        result,
+                        call("mul", vec![overflow, utils::bit_mask(bits)?.into()])?,
+                    ],
+                )?,
+                _ => call("mul", vec![result, call("iszero", vec![overflow])?])?,
+            }
+        };
+
+        self.declare_results(vec![value])
+    }
+
+    fn arg_location(&self, index: usize) -> Result<DataLocation> {
+        let (arg, _) = self.call.arguments.get(index).ok_or(anyhow!(
+            "{} intrinsic: {}",
+            error::WRONG_ARG,
+            self.call
+        ))?;
+
+        let ty = self.types.type_of(arg);
+
+        Ok(utils::pointer_location(&ty, self.config))
+    }
+
+    fn arg_bits(&self, index: usize) -> Result<u32> {
+        let (arg, _) = self.call.arguments.get(index).ok_or(anyhow!(
+            "{} intrinsic: {}",
+            error::WRONG_ARG,
+            self.call
+        ))?;
+
+        match self.types.type_of(arg).as_ref() {
+            Type::IntegerType { bits } if *bits <= 256 => Ok(*bits),
+            ty => Err(anyhow!("{} intrinsic: {ty}", error::UNSUPPERTED_OPERAND)),
+        }
+    }
+
+    /// Declare flattened dest of call.
+    fn declare_results(&self, values: Vec<Value>) -> Result<Vec<Statement>> {
+        let dest = self.call.dest.as_ref().ok_or(anyhow!(
+            "{} intrinsic: {}",
+            error::WRONG_ARG,
+            self.call
+        ))?;
+
+        let ty = self.types.type_of(self.call);
+
+        let flatter = TypeFlatter::new(self.types, self.config);
+        let names = flatter.flatten_parameter(dest, &ty)?;
+
+        if names.len() != values.len() {
+            return Err(anyhow!("{} intrinsic: {}", error::WRONG_ARG, self.call));
+        }
+
+        Ok(names
+            .into_iter()
+            .zip(values)
+            .map(|(name, value)| {
+                VariableDeclare {
+                    names: vec![name],
+                    value,
+                }
+                .into()
+            })
+            .collect())
+    }
+}
+
+fn call(name: &str, args: Vec<Value>) -> Result<Value> {
+    Ok(utils::builtin_call(name, args)?.into())
+}
+
+fn build_unsigned_overflow(op: &str, a: Value, b: Value, bits: u32) -> Result<(Value, Value)> {
+    let full = call(op, vec![a.clone(), b.clone()])?;
+    let result = utils::mask_bits(full.clone(), bits)?;
+
+    let overflow = match op {
+        // Wrapped result is less than operand.
+        "add" => call("lt", vec![result.clone(), a])?,
+        "sub" => call("lt", vec![a, b])?,
+        // Product of two 128 bits integers never wraps 256 bits.
+        "mul" if bits <= 128 => call("gt", vec![full, utils::bit_mask(bits)?.into()])?,
+        "mul" => {
+            let exact = call("eq", vec![call("div", vec![full.clone(), a.clone()])?, b])?;
+            let exact = call("and", vec![exact, in_range(full, bits)?])?;
+
+            call(
+                "iszero",
+                vec![call("or", vec![call("iszero", vec![a])?, exact])?],
+            )?
+        }
+        _ => return Err(anyhow!("Unsupported overflow operator: {op}")),
+    };
+
+    Ok((result, overflow))
+}
+
+fn build_signed_overflow(op: &str, a: Value, b: Value, bits: u32) -> Result<(Value, Value)> {
+    let a = utils::sign_extend(a, bits)?;
+    let b = utils::sign_extend(b, bits)?;
+
+    let full = call(op, vec![a.clone(), b.clone()])?;
+    let result = utils::mask_bits(full.clone(), bits)?;
+
+    let overflow = match op {
+        // Sum of two narrower integers never wraps 256 bits, check it's in range of bit width.
+        "add" | "sub" if bits < 256 => not_sign_extended(full, bits)?,
+        "add" => call(
+            "xor",
+            vec![call("slt", vec![b, num(0)?])?, call("slt", vec![full, a])?],
+        )?,
+        "sub" => call(
+            "xor",
+            vec![call("sgt", vec![b, num(0)?])?, call("slt", vec![full, a])?],
+        )?,
+        "mul" if bits <= 128 => not_sign_extended(full, bits)?,
+        "mul" => {
+            // `sdiv(MIN, -1)` is `MIN` in EVM, so this case is checked separately.
+            let min = call("shl", vec![num(255)?, num(1)?])?;
+            let min_neg = call(
+                "and",
+                vec![
+                    call("eq", vec![a.clone(), call("not", vec![num(0)?])?])?,
+                    call("eq", vec![b.clone(), min])?,
+                ],
+            )?;
+
+            let inexact = call(
+                "iszero",
+                vec![call(
+                    "eq",
+                    vec![call("sdiv", vec![full.clone(), a.clone()])?, b],
+                )?],
+            )?;
+            let inexact = call(
+                "and",
+                vec![call("iszero", vec![call("iszero", vec![a])?])?, inexact],
+            )?;
+
+            let mut overflow = call("or", vec![inexact, min_neg])?;
+
+            if bits < 256 {
+                overflow = call("or", vec![overflow, not_sign_extended(full, bits)?])?;
+            }
+
+            overflow
+        }
+        _ => return Err(anyhow!("Unsupported overflow operator: {op}")),
+    };
+
+    Ok((result, overflow))
+}
+
+/// Branch free select, `cond` must be 0 or 1.
+fn select(cond: Value, a: Value, b: Value) -> Result<Value> {
+    let diff = call("xor", vec![a, b.clone()])?;
+
+    call("xor", vec![b, call("mul", vec![diff, cond])?])
+}
+
+/// Max signed value of `bits` bits.
+fn signed_max(bits: u32) -> Result<Value> {
+    if bits <= 1 {
+        return num(0);
+    }
+
+    Ok(utils::bit_mask(bits - 1)?.into())
+}
+
+/// Unsigned value is less than `2^bits`.
+fn in_range(value: Value, bits: u32) -> Result<Value> {
+    if bits >= 256 {
+        return num(1);
+    }
+
+    call(
+        "iszero",
+        vec![call("gt", vec![value, utils::bit_mask(bits)?.into()])?],
+    )
+}
+
+/// Signed value is out of range of `bits` bits.
+fn not_sign_extended(value: Value, bits: u32) -> Result<Value> {
+    let extended = utils::sign_extend(utils::mask_bits(value.clone(), bits)?, bits)?;
+
+    call("iszero", vec![call("eq", vec![extended, value])?])
+}
+
+fn num(n: u64) -> Result<Value> {
+    Ok(Literal::int_number(n)?.into())
+}
+
+/// Name of called function if it is LLVM intrinsic function.
+pub fn intrinsic_name(call: &Call) -> Option<&str> {
+    let operand = call.function.as_ref().right()?;
+
+    match operand.as_constant()? {
+        Constant::GlobalReference {
+            name: Name::Name(name),
+            ty: _,
+        } if name.starts_with("llvm.") => Some(name.as_str()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yuler::Writer;
+
+    use crate::{Compiler, Config, IntSemantics};
+
+    fn compile_wide(ir: &str, entry: &str) -> String {
+        let config = Config::default().int_semantics(IntSemantics::Wide);
+        let object = Compiler::new(config)
+            .compile_object_from_ir(ir, entry)
+            .unwrap();
+
+        let mut w = Writer::new(Vec::new(), "");
+        object.write(&mut w).unwrap();
+        String::from_utf8(w.w).unwrap()
+    }
+
+    #[test]
+    fn test_bit_helpers_keep_bits_in_wide() {
+        for (name, params, args) in [
+            ("bswap", "i64", "i64 %a"),
+            ("bitreverse", "i64", "i64 %a"),
+            ("ctpop", "i64", "i64 %a"),
+            ("ctlz", "i64, i1", "i64 %a, i1 false"),
+            ("cttz", "i64, i1", "i64 %a, i1 false"),
+        ] {
+            let ir = format!(
+                r#"
+                declare i64 @llvm.{name}.i64({params})
+
+                define i64 @f(i64 %a) {{
+                  %r = call i64 @llvm.{name}.i64({args})
+                  ret i64 %r
+                }}
+                "#
+            );
+
+            let yul = compile_wide(&ir, "f");
+
+            assert!(
+                yul.contains(&format!(
+                    "let r := __yn_{name}(and(a, 0xffffffffffffffff), 64)"
+                )),
+                "{yul}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_funnel_shift_keeps_bits_in_wide() {
+        let yul = compile_wide(
+            r#"
+            declare i64 @llvm.fshl.i64(i64, i64, i64)
+
+            define i64 @f(i64 %a, i64 %b, i64 %c) {
+              %r = call i64 @llvm.fshl.i64(i64 %a, i64 %b, i64 %c)
+              ret i64 %r
+            }
+            "#,
+            "f",
+        );
+
+        assert!(
+            yul.contains(
+                "let r := __yn_fshl(and(a, 0xffffffffffffffff), and(b, 0xffffffffffffffff), \
+                 and(c, 0xffffffffffffffff), 64)"
+            ),
+            "{yul}"
+        );
+    }
+
+    #[test]
+    fn test_overflow_keeps_bits_in_wide() {
+        let yul = compile_wide(
+            r#"
+            declare {i64, i1} @llvm.uadd.with.overflow.i64(i64, i64)
+
+            define i1 @f(i64 %a, i64 %b) {
+              %r = call {i64, i1} @llvm.uadd.with.overflow.i64(i64 %a, i64 %b)
+              %o = extractvalue {i64, i1} %r, 1
+              ret i1 %o
+            }
+            "#,
+            "f",
+        );
+
+        assert!(
+            yul.contains(
+                "let r_1 := lt(and(add(and(a, 0xffffffffffffffff), and(b, 0xffffffffffffffff)), \
+                 0xffffffffffffffff), and(a, 0xffffffffffffffff))"
+            ),
+            "{yul}"
+        );
+    }
+
+    #[test]
+    fn test_saturating_keeps_bits_in_wide() {
+        let yul = compile_wide(
+            r#"
+            declare i8 @llvm.usub.sat.i8(i8, i8)
+
+            define i8 @f(i8 %a, i8 %b) {
+              %r = call i8 @llvm.usub.sat.i8(i8 %a, i8 %b)
+              ret i8 %r
+            }
+            "#,
+            "f",
+        );
+
+        assert!(
+            yul.contains(
+                "let r := mul(and(sub(and(a, 0xff), and(b, 0xff)), 0xff), \
+                 iszero(lt(and(a, 0xff), and(b, 0xff))))"
+            ),
+            "{yul}"
+        );
+    }
+
+    #[test]
+    fn test_wrapping_add_in_wide() {
+        let yul = compile_wide(
+            r#"
+            define i64 @f(i64 %a, i64 %b) {
+              %r = add i64 %a, %b
+              ret i64 %r
+            }
+            "#,
+            "f",
+        );
+
+        assert!(yul.contains("let r := add(a, b)"), "{yul}");
+    }
+}
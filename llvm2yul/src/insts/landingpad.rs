@@ -0,0 +1,41 @@
+use anyhow::Result;
+use llvm_ir::{instruction::LandingPad, types::Types};
+use yuler::{Literal, Statement, VariableDeclare};
+
+use crate::{Config, TypeFlatter};
+
+/// Exception of landing pad is never thrown by EVM, so its values are `0`.
+pub struct LandingPadCompiler<'a> {
+    inst: &'a LandingPad,
+    types: &'a Types,
+    config: &'a Config,
+}
+
+impl<'a> LandingPadCompiler<'a> {
+    pub fn new(inst: &'a LandingPad, types: &'a Types, config: &'a Config) -> Self {
+        Self {
+            inst,
+            types,
+            config,
+        }
+    }
+
+    pub fn compile(&self) -> Result<Vec<Statement>> {
+        let flatter = TypeFlatter::new(self.types, self.config);
+        let names = flatter.flatten_parameter(&self.inst.dest, &self.inst.result_type)?;
+
+        let mut res = Vec::new();
+
+        for name in names {
+            res.push(
+                VariableDeclare {
+                    names: vec![name],
+                    value: Literal::int_number(0)?.into(),
+                }
+                .into(),
+            );
+        }
+
+        Ok(res)
+    }
+}
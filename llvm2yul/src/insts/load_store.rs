@@ -0,0 +1,105 @@
+use anyhow::{anyhow, Result};
+use llvm_ir::{
+    instruction::{Load, Store},
+    types::Types,
+};
+use yuler::{Statement, VariableDeclare};
+
+use crate::{error, utils, Config, OperandFlatter, TypeFlatter};
+
+pub struct LoadCompiler<'a> {
+    inst: &'a Load,
+    types: &'a Types,
+    config: &'a Config,
+}
+
+impl<'a> LoadCompiler<'a> {
+    pub fn new(inst: &'a Load, types: &'a Types, config: &'a Config) -> Self {
+        Self {
+            inst,
+            types,
+            config,
+        }
+    }
+
+    pub fn compile(&self) -> Result<Vec<Statement>> {
+        let type_flatter = TypeFlatter::new(self.types, self.config);
+        let operand_flatter = OperandFlatter::new(self.types, self.config);
+
+        let names = type_flatter.flatten_parameter(&self.inst.dest, &self.inst.loaded_ty)?;
+        let layout = type_flatter.compute_layout(&self.inst.loaded_ty)?;
+        let address = operand_flatter.flatten_single(&self.inst.address)?;
+
+        let ty = self.types.type_of(&self.inst.address);
+        let location = utils::pointer_location(&ty, self.config);
+
+        let mut res = Vec::new();
+
+        for (name, (offset, size)) in names.into_iter().zip(layout) {
+            let address = utils::offset_address(&address, offset)?;
+
+            let (mut pre, value) = utils::load_from(self.config, location, address, size)?;
+
+            res.append(&mut pre);
+            res.push(
+                VariableDeclare {
+                    names: vec![name],
+                    value,
+                }
+                .into(),
+            );
+        }
+
+        Ok(res)
+    }
+}
+
+pub struct StoreCompiler<'a> {
+    inst: &'a Store,
+    types: &'a Types,
+    config: &'a Config,
+}
+
+impl<'a> StoreCompiler<'a> {
+    pub fn new(inst: &'a Store, types: &'a Types, config: &'a Config) -> Self {
+        Self {
+            inst,
+            types,
+            config,
+        }
+    }
+
+    pub fn compile(&self) -> Result<Vec<Statement>> {
+        let type_flatter = TypeFlatter::new(self.types, self.config);
+        let operand_flatter = OperandFlatter::new(self.types, self.config);
+
+        let ty = self.types.type_of(&self.inst.value);
+
+        let values = operand_flatter.flatten(&self.inst.value)?;
+        let layout = type_flatter.compute_layout(&ty)?;
+        let address = operand_flatter.flatten_single(&self.inst.address)?;
+
+        if values.len() != layout.len() {
+            return Err(anyhow!("{} store: {}", error::WRONG_ARG, self.inst));
+        }
+
+        let address_ty = self.types.type_of(&self.inst.address);
+        let location = utils::pointer_location(&address_ty, self.config);
+
+        let mut res = Vec::new();
+
+        for (value, (offset, size)) in values.into_iter().zip(layout) {
+            let address = utils::offset_address(&address, offset)?;
+
+            res.push(utils::store_to(
+                self.config,
+                location,
+                address,
+                value,
+                size,
+            )?);
+        }
+
+        Ok(res)
+    }
+}
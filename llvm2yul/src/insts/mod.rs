@@ -18,3 +18,27 @@ pub use ptr_int::*;
 
 mod get_element_ptr;
 pub use get_element_ptr::*;
+
+mod phi;
+pub use phi::*;
+
+mod load_store;
+pub use load_store::*;
+
+mod binary;
+pub use binary::*;
+
+mod icmp;
+pub use icmp::*;
+
+mod cast;
+pub use cast::*;
+
+mod intrinsic;
+pub use intrinsic::*;
+
+mod freeze;
+pub use freeze::*;
+
+mod landingpad;
+pub use landingpad::*;
@@ -0,0 +1,196 @@
+use anyhow::{anyhow, Result};
+use llvm_ir::{instruction::Phi, types::Types, BasicBlock, Function, Instruction, Name};
+use yuler::{Assignment, Ident, Literal, Statement, Value, VariableDeclare};
+
+use crate::{error, utils, Config, OperandFlatter, TypeFlatter};
+
+/// Destruct SSA form.
+///
+/// Each phi is declared as variables at the beginning of function, then every
+/// incoming edge assigns these variables before jumping into the block.
+pub struct PhiCompiler<'a> {
+    llvm_func: &'a Function,
+    types: &'a Types,
+    config: &'a Config,
+}
+
+impl<'a> PhiCompiler<'a> {
+    pub fn new(llvm_func: &'a Function, types: &'a Types, config: &'a Config) -> Self {
+        Self {
+            llvm_func,
+            types,
+            config,
+        }
+    }
+
+    pub fn compile_declares(&self) -> Result<Vec<Statement>> {
+        let flatter = TypeFlatter::new(self.types, self.config);
+
+        let mut res = Vec::new();
+
+        for bb in &self.llvm_func.basic_blocks {
+            for phi in phis(bb) {
+                for name in flatter.flatten_parameter(&phi.dest, &phi.to_type)? {
+                    res.push(
+                        VariableDeclare {
+                            names: vec![name],
+                            value: Literal::int_number(0)?.into(),
+                        }
+                        .into(),
+                    );
+                }
+            }
+        }
+
+        Ok(res)
+    }
+
+    /// Build copies for edge `from` -> `to`.
+    pub fn compile_edge(&self, from: &Name, to: &Name) -> Result<Vec<Statement>> {
+        let bb = self
+            .llvm_func
+            .get_bb_by_name(to)
+            .ok_or(anyhow!("Fatal: Failed to get basic block: {to}"))?;
+
+        let type_flatter = TypeFlatter::new(self.types, self.config);
+        let operand_flatter = OperandFlatter::new(self.types, self.config);
+
+        let mut copies = Vec::new();
+
+        for phi in phis(bb) {
+            let (operand, _) =
+                phi.incoming_values
+                    .iter()
+                    .find(|(_, n)| n == from)
+                    .ok_or(anyhow!(
+                        "{} phi {}: no value from {from}",
+                        error::WRONG_ARG,
+                        phi.dest
+                    ))?;
+
+            let dests = type_flatter.flatten_parameter(&phi.dest, &phi.to_type)?;
+            let values = operand_flatter.flatten(operand)?;
+
+            if dests.len() != values.len() {
+                return Err(anyhow!("{} phi: {}", error::WRONG_ARG, phi));
+            }
+
+            copies.extend(dests.into_iter().zip(values));
+        }
+
+        let tmp_prefix = format!(
+            "__yn_phi_{}_{}",
+            utils::yul_ident_name(from),
+            utils::yul_ident_name(to)
+        );
+
+        sequentialize_copies(copies, &tmp_prefix)
+    }
+}
+
+fn phis(bb: &BasicBlock) -> impl Iterator<Item = &Phi> {
+    bb.instrs.iter().map_while(|inst| match inst {
+        Instruction::Phi(phi) => Some(phi),
+        _ => None,
+    })
+}
+
+fn reads(value: &Value, ident: &Ident) -> bool {
+    matches!(value, Value::Variable(v) if v.0 == ident.0)
+}
+
+/// Turn parallel copies into sequential assignments.
+///
+/// A copy is emitted once its destination is no longer read by other pending copies. When only
+/// cycles remain, like a swap, the old value of a destination is saved into a temporary.
+pub(crate) fn sequentialize_copies(
+    copies: Vec<(Ident, Value)>,
+    tmp_prefix: &str,
+) -> Result<Vec<Statement>> {
+    let mut pending: Vec<(Ident, Value)> = copies
+        .into_iter()
+        .filter(|(dest, value)| !reads(value, dest))
+        .collect();
+
+    let mut res = Vec::new();
+    let mut tmp_index = 0;
+
+    while !pending.is_empty() {
+        let ready = pending
+            .iter()
+            .position(|(dest, _)| !pending.iter().any(|(_, v)| reads(v, dest)));
+
+        if let Some(pos) = ready {
+            let (dest, value) = pending.remove(pos);
+
+            res.push(
+                Assignment {
+                    names: vec![dest],
+                    value,
+                }
+                .into(),
+            );
+        } else {
+            let dest = pending[0].0.clone();
+            let tmp = Ident::new(format!("{tmp_prefix}_{tmp_index}"))?;
+            tmp_index += 1;
+
+            res.push(
+                VariableDeclare {
+                    names: vec![tmp.clone()],
+                    value: dest.clone().into(),
+                }
+                .into(),
+            );
+
+            for (_, v) in pending.iter_mut() {
+                if reads(v, &dest) {
+                    *v = tmp.clone().into();
+                }
+            }
+        }
+    }
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use yuler::{Block, Ident, Literal, Writer};
+
+    use super::sequentialize_copies;
+
+    fn write(copies: Vec<(&str, yuler::Value)>) -> String {
+        let copies = copies
+            .into_iter()
+            .map(|(d, v)| (Ident::new(d).unwrap(), v))
+            .collect();
+
+        let stmts = sequentialize_copies(copies, "tmp").unwrap();
+
+        let mut w = Writer::new(Vec::new(), "");
+        Block(stmts).write(&mut w).unwrap();
+        String::from_utf8(w.w).unwrap()
+    }
+
+    #[test]
+    fn test_chain() {
+        let res = write(vec![
+            ("a", Ident::new("b").unwrap().into()),
+            ("b", Literal::int_number(1).unwrap().into()),
+        ]);
+
+        assert_eq!(res, "{\na := b\nb := 1\n}");
+    }
+
+    #[test]
+    fn test_swap() {
+        let res = write(vec![
+            ("a", Ident::new("b").unwrap().into()),
+            ("b", Ident::new("a").unwrap().into()),
+            ("c", Ident::new("c").unwrap().into()),
+        ]);
+
+        assert_eq!(res, "{\nlet tmp_0 := a\na := b\nb := tmp_0\n}");
+    }
+}
@@ -1,16 +1,25 @@
 use anyhow::{anyhow, Result};
-use llvm_ir::{instruction::Select, Constant, Operand, Type};
-use yuler::{Assignment, Ident, If, Literal, Statement, Value, VariableDeclare};
+use llvm_ir::{instruction::Select, types::Types, Operand, Type};
+use yuler::{Assignment, Ident, If, Statement, Value, VariableDeclare};
 
-use crate::utils::{self, yul_ident_name};
+use crate::{
+    utils::{self, yul_ident_name},
+    Config, ConstantFlatter,
+};
 
 pub struct SelectCompiler<'a> {
     select: &'a Select,
+    types: &'a Types,
+    config: &'a Config,
 }
 
 impl<'a> SelectCompiler<'a> {
-    pub fn new(select: &'a Select) -> Self {
-        Self { select }
+    pub fn new(select: &'a Select, types: &'a Types, config: &'a Config) -> Self {
+        Self {
+            select,
+            types,
+            config,
+        }
     }
 
     pub fn compile(&self) -> Result<Vec<Statement>> {
@@ -37,7 +46,7 @@ impl<'a> SelectCompiler<'a> {
         res.push(
             VariableDeclare {
                 names: vec![dest.clone()],
-                value: build_operand_value_for_select(&self.select.false_value)?,
+                value: self.build_operand_value(&self.select.false_value)?,
             }
             .into(),
         );
@@ -47,7 +56,7 @@ impl<'a> SelectCompiler<'a> {
                 cond: name.into(),
                 block: vec![Assignment {
                     names: vec![dest],
-                    value: build_operand_value_for_select(&self.select.true_value)?,
+                    value: self.build_operand_value(&self.select.true_value)?,
                 }
                 .into()]
                 .into(),
@@ -57,29 +66,32 @@ impl<'a> SelectCompiler<'a> {
 
         Ok(res)
     }
-}
 
-fn build_operand_value_for_select(operand: &Operand) -> Result<Value> {
-    match operand {
-        // TODO: flatten operand
-        Operand::LocalOperand { name, ty } => match ty.as_ref() {
-            Type::IntegerType { bits: _ } => {
-                let name = Ident::new(yul_ident_name(name))?;
+    fn build_operand_value(&self, operand: &Operand) -> Result<Value> {
+        match operand {
+            // TODO: flatten operand
+            Operand::LocalOperand { name, ty } => match ty.as_ref() {
+                Type::IntegerType { bits: _ } => {
+                    let name = Ident::new(yul_ident_name(name))?;
 
-                Ok(name.into())
-            }
-            Type::PointerType { addr_space: _ } => {
-                let name = Ident::new(yul_ident_name(name))?;
+                    Ok(name.into())
+                }
+                Type::PointerType { addr_space: _ } => {
+                    let name = Ident::new(yul_ident_name(name))?;
 
-                Ok(name.into())
+                    Ok(name.into())
+                }
+                _ => Err(anyhow!("Unsupported local type: {operand}")),
+            },
+            Operand::ConstantOperand(constant) => {
+                let values = ConstantFlatter::new(self.types, self.config).flatten(constant)?;
+
+                match values.as_slice() {
+                    [value] => Ok(value.clone()),
+                    _ => Err(anyhow!("Unsupported constant type: {constant}")),
+                }
             }
-            _ => Err(anyhow!("Unsupported local type: {operand}")),
-        },
-        // TODO: flatten constant
-        Operand::ConstantOperand(constant) => match constant.as_ref() {
-            Constant::Int { bits: _, value } => Ok(Literal::int_number(*value)?.into()),
-            _ => Err(anyhow!("Unsupported constant type: {constant}")),
-        },
-        _ => Err(anyhow!("Unsupported operand for select: {operand}")),
+            _ => Err(anyhow!("Unsupported operand for select: {operand}")),
+        }
     }
 }
@@ -16,9 +16,15 @@ pub use types::*;
 mod constant;
 pub use constant::*;
 
+mod global;
+pub use global::*;
+
 mod config;
 pub use config::*;
 
+mod operand;
+pub use operand::*;
+
 pub mod utils;
 
 pub mod error;
@@ -0,0 +1,49 @@
+use anyhow::{anyhow, Result};
+use llvm_ir::{types::Types, Operand};
+use yuler::Value;
+
+use crate::{error, Config, ConstantFlatter, TypeFlatter};
+
+pub struct OperandFlatter<'a> {
+    types: &'a Types,
+    config: &'a Config,
+}
+
+impl<'a> OperandFlatter<'a> {
+    pub fn new(types: &'a Types, config: &'a Config) -> Self {
+        Self { types, config }
+    }
+
+    /// Flatten operand into values, struct and array will be expanded.
+    pub fn flatten(&self, operand: &Operand) -> Result<Vec<Value>> {
+        match operand {
+            Operand::LocalOperand { name, ty } => {
+                let flatter = TypeFlatter::new(self.types, self.config);
+                let names = flatter.flatten_parameter(name, ty)?;
+
+                Ok(names.into_iter().map(Value::from).collect())
+            }
+            Operand::ConstantOperand(constant) => {
+                let flatter = ConstantFlatter::new(self.types, self.config);
+
+                flatter.flatten(constant)
+            }
+            _ => Err(anyhow!(
+                "{} operand flatten: {}",
+                error::UNSUPPERTED_OPERAND,
+                operand
+            )),
+        }
+    }
+
+    /// Flatten operand which must be a single value.
+    pub fn flatten_single(&self, operand: &Operand) -> Result<Value> {
+        let mut values = self.flatten(operand)?;
+
+        if values.len() != 1 {
+            return Err(anyhow!("{} single operand: {}", error::WRONG_ARG, operand));
+        }
+
+        Ok(values.remove(0))
+    }
+}
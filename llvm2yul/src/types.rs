@@ -1,7 +1,7 @@
 use anyhow::{anyhow, Result};
 use llvm_ir::{
     types::{NamedStructDef, Types},
-    Name, Type,
+    Name, Type, TypeRef,
 };
 use yuler::Ident;
 
@@ -36,6 +36,27 @@ impl<'a> TypeFlatter<'a> {
         Ok(tokens)
     }
 
+    /// Types of elements of aggregate, named struct is resolved to its definition.
+    pub fn element_types(&self, ty: &Type) -> Result<Vec<TypeRef>> {
+        match ty {
+            Type::ArrayType {
+                element_type,
+                num_elements,
+            } => Ok(vec![element_type.clone(); *num_elements]),
+            Type::StructType {
+                element_types,
+                is_packed: _,
+            } => Ok(element_types.clone()),
+            Type::NamedStructType { name } if !self.config.basic_types.contains(name) => {
+                match self.types.named_struct_def(name) {
+                    Some(NamedStructDef::Defined(ty)) => self.element_types(ty),
+                    _ => Err(anyhow!("Linked error, no opaque supported")),
+                }
+            }
+            _ => Err(anyhow!("{} aggregate: {}", error::WRONG_ARG, ty)),
+        }
+    }
+
     fn _iter_type(
         &self,
         tokens: &mut Vec<Ident>,
@@ -45,6 +66,14 @@ impl<'a> TypeFlatter<'a> {
     ) -> Result<()> {
         let ident = Ident::new(name)?;
 
+        if let Some(len) = byte_array_len(ty) {
+            for i in 0..len.div_ceil(32) {
+                tokens.push(Ident::new(format!("{}_{}", name, i))?);
+            }
+
+            return Ok(());
+        }
+
         match ty {
             Type::VoidType => {
                 if !void_generated {
@@ -81,15 +110,13 @@ impl<'a> TypeFlatter<'a> {
                     self._iter_type(tokens, &added_name, e, void_generated)?;
                 }
             }
-            Type::NamedStructType { name } => {
-                let name = &name.to_string();
-
-                if self.config.basic_types.contains(name) {
+            Type::NamedStructType { name: struct_name } => {
+                if self.config.basic_types.contains(struct_name) {
                     tokens.push(ident);
                 } else {
                     let ty = self
                         .types
-                        .named_struct_def(name)
+                        .named_struct_def(struct_name)
                         .ok_or(anyhow!("Linked error, failed to get named struct type."))?;
 
                     if let NamedStructDef::Defined(ty) = ty {
@@ -111,9 +138,95 @@ impl<'a> TypeFlatter<'a> {
         Ok(())
     }
 
+    /// Size of type in memory.
+    ///
+    /// Integer of 8 bits or less takes 1 byte, other integers and pointers take a 32 bytes
+    /// word. Array and struct are laid out element by element without padding.
     pub fn compute_size(&self, ty: &Type) -> Result<u64> {
         _compute_size(ty, self.types, self.config)
     }
+
+    /// Offset and size of each flattened value in memory, in the order of `flatten_parameter`.
+    pub fn compute_layout(&self, ty: &Type) -> Result<Vec<(u64, u64)>> {
+        let mut layout = Vec::new();
+
+        self._iter_layout(&mut layout, 0, ty)?;
+
+        Ok(layout)
+    }
+
+    fn _iter_layout(&self, layout: &mut Vec<(u64, u64)>, offset: u64, ty: &Type) -> Result<u64> {
+        if let Some(len) = byte_array_len(ty) {
+            let len = len as u64;
+
+            for start in (0..len).step_by(32) {
+                layout.push((offset + start, (len - start).min(32)));
+            }
+
+            return Ok(len);
+        }
+
+        match ty {
+            Type::ArrayType {
+                element_type,
+                num_elements,
+            } => {
+                let mut size = 0;
+
+                for _ in 0..*num_elements {
+                    size += self._iter_layout(layout, offset + size, element_type)?;
+                }
+
+                Ok(size)
+            }
+            Type::StructType {
+                element_types,
+                is_packed: _,
+            } => {
+                let mut size = 0;
+
+                for e in element_types {
+                    size += self._iter_layout(layout, offset + size, e)?;
+                }
+
+                Ok(size)
+            }
+            Type::NamedStructType { name } if !self.config.basic_types.contains(name) => {
+                let ty = self
+                    .types
+                    .named_struct_def(name)
+                    .ok_or(anyhow!("Linked error, failed to get named struct type."))?;
+
+                if let NamedStructDef::Defined(ty) = ty {
+                    self._iter_layout(layout, offset, ty)
+                } else {
+                    Err(anyhow!("Linked error, no opaque supported"))
+                }
+            }
+            Type::VoidType => Err(anyhow!("Unspported Void Type")),
+            _ => {
+                let size = self.compute_size(ty)?;
+
+                layout.push((offset, size));
+
+                Ok(size)
+            }
+        }
+    }
+}
+
+/// Length of `[N x i8]`, which is packed into words of 32 bytes instead of flattened by element.
+///
+/// Word keeps its bytes as a big endian number, so the last word shorter than 32 bytes is right
+/// aligned, same as an integer of that size.
+pub fn byte_array_len(ty: &Type) -> Option<usize> {
+    match ty {
+        Type::ArrayType {
+            element_type,
+            num_elements,
+        } if matches!(element_type.as_ref(), Type::IntegerType { bits: 8 }) => Some(*num_elements),
+        _ => None,
+    }
 }
 
 fn _compute_size(ty: &Type, types: &Types, config: &Config) -> Result<u64> {
@@ -1,16 +1,12 @@
-pub fn builtin_args_num(name: &str) -> Option<usize> {
-    if name.len() < 5 {
-        return None;
-    }
-
-    let (prefix, name) = name.split_at(6);
+use anyhow::Result;
+use yuler::{FunctionCall, Ident, Value};
 
-    if prefix != "__yul_" {
-        return None;
-    }
+pub fn builtin_args_num(name: &str) -> Option<usize> {
+    let name = name.strip_prefix("__yul_")?;
 
     let r = match name {
         "_ext_literal" => 4,
+        "_ext_literal_str" => 1,
         "stop" => 0,
         "add" => 2,
         "sub" => 2,
@@ -105,3 +101,11 @@ pub fn builtin_args_num(name: &str) -> Option<usize> {
 pub fn is_builtin(name: &str) -> bool {
     builtin_args_num(name).is_some()
 }
+
+/// Build call of yul builtin function, `name` is without `__yul_` prefix.
+pub fn builtin_call(name: &str, args: Vec<Value>) -> Result<FunctionCall> {
+    Ok(FunctionCall {
+        name: Ident::new(name)?,
+        args,
+    })
+}
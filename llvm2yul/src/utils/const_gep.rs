@@ -0,0 +1,214 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use llvm_ir::{Constant, ConstantRef, Module, TypeRef};
+use llvm_sys::{
+    core::{
+        LLVMAddGlobalInAddressSpace, LLVMConstGEP2, LLVMConstInBoundsGEP2, LLVMConstNull,
+        LLVMConstStructInContext, LLVMGetConstOpcode, LLVMGetFirstBasicBlock, LLVMGetFirstFunction,
+        LLVMGetFirstGlobal, LLVMGetFirstInstruction, LLVMGetGEPSourceElementType,
+        LLVMGetInitializer, LLVMGetModuleContext, LLVMGetNextBasicBlock, LLVMGetNextFunction,
+        LLVMGetNextGlobal, LLVMGetNextInstruction, LLVMGetNumOperands, LLVMGetOperand,
+        LLVMGetPointerAddressSpace, LLVMGetTypeKind, LLVMIsAConstantArray, LLVMIsAConstantExpr,
+        LLVMIsAConstantStruct, LLVMIsAConstantVector, LLVMIsInBounds, LLVMIsNull,
+        LLVMReplaceAllUsesWith, LLVMSetGlobalConstant, LLVMSetInitializer, LLVMSetLinkage,
+        LLVMTypeOf,
+    },
+    prelude::{LLVMModuleRef, LLVMValueRef},
+    LLVMLinkage, LLVMOpcode, LLVMTypeKind,
+};
+
+use super::yul_ident_name;
+
+pub const GEP_PREFIX: &str = "__yn_gep_";
+
+/// Keep source element type of every constant `getelementptr` with non-zero index, which
+/// `llvm_ir` drops with opaque pointer.
+///
+/// Each one gets a private constant global `{ T zeroinitializer, ptr address, indices.. }`, named
+/// with `GEP_PREFIX`, and its address is replaced with the marker. So every `getelementptr` has
+/// its own marker even if they only differ in `T`. `gep_source_types` reads them back after
+/// `llvm_ir` parses module.
+pub(super) unsafe fn mark_const_geps(module: LLVMModuleRef) {
+    let context = LLVMGetModuleContext(module);
+
+    let geps = const_exprs(module, LLVMOpcode::LLVMGetElementPtr);
+
+    // Users come before operands, so a `getelementptr` isn't rebuilt before it's replaced.
+    for (i, gep) in geps.into_iter().rev().enumerate() {
+        let address = LLVMGetOperand(gep, 0);
+        let num_indices = LLVMGetNumOperands(gep) as u32 - 1;
+
+        let mut indices: Vec<_> = (1..=num_indices).map(|j| LLVMGetOperand(gep, j)).collect();
+
+        if indices.iter().all(|index| LLVMIsNull(*index) != 0)
+            || LLVMGetTypeKind(LLVMTypeOf(address)) != LLVMTypeKind::LLVMPointerTypeKind
+        {
+            continue;
+        }
+
+        let ty = LLVMGetGEPSourceElementType(gep);
+
+        let mut values = vec![LLVMConstNull(ty), address];
+        values.extend(&indices);
+
+        let init = LLVMConstStructInContext(context, values.as_mut_ptr(), values.len() as u32, 0);
+
+        let name = format!("{GEP_PREFIX}{i}\0");
+        let marker = LLVMAddGlobalInAddressSpace(
+            module,
+            LLVMTypeOf(init),
+            name.as_ptr() as *const _,
+            LLVMGetPointerAddressSpace(LLVMTypeOf(address)),
+        );
+
+        LLVMSetInitializer(marker, init);
+        LLVMSetGlobalConstant(marker, 1);
+        LLVMSetLinkage(marker, LLVMLinkage::LLVMPrivateLinkage);
+
+        let replaced = if LLVMIsInBounds(gep) != 0 {
+            LLVMConstInBoundsGEP2(ty, marker, indices.as_mut_ptr(), num_indices)
+        } else {
+            LLVMConstGEP2(ty, marker, indices.as_mut_ptr(), num_indices)
+        };
+
+        LLVMReplaceAllUsesWith(gep, replaced);
+    }
+}
+
+/// Constant expressions of `opcode` in module, operands come before their users.
+unsafe fn const_exprs(module: LLVMModuleRef, opcode: LLVMOpcode) -> Vec<LLVMValueRef> {
+    let mut seen = BTreeSet::new();
+    let mut exprs = Vec::new();
+
+    let mut global = LLVMGetFirstGlobal(module);
+    while !global.is_null() {
+        collect_exprs(LLVMGetInitializer(global), opcode, &mut seen, &mut exprs);
+
+        global = LLVMGetNextGlobal(global);
+    }
+
+    let mut func = LLVMGetFirstFunction(module);
+    while !func.is_null() {
+        let mut bb = LLVMGetFirstBasicBlock(func);
+        while !bb.is_null() {
+            let mut inst = LLVMGetFirstInstruction(bb);
+            while !inst.is_null() {
+                for i in 0..LLVMGetNumOperands(inst) {
+                    collect_exprs(
+                        LLVMGetOperand(inst, i as u32),
+                        opcode,
+                        &mut seen,
+                        &mut exprs,
+                    );
+                }
+
+                inst = LLVMGetNextInstruction(inst);
+            }
+
+            bb = LLVMGetNextBasicBlock(bb);
+        }
+
+        func = LLVMGetNextFunction(func);
+    }
+
+    exprs
+}
+
+unsafe fn collect_exprs(
+    c: LLVMValueRef,
+    opcode: LLVMOpcode,
+    seen: &mut BTreeSet<usize>,
+    exprs: &mut Vec<LLVMValueRef>,
+) {
+    if c.is_null() {
+        return;
+    }
+
+    let is_expr = !LLVMIsAConstantExpr(c).is_null();
+
+    if !is_expr
+        && LLVMIsAConstantStruct(c).is_null()
+        && LLVMIsAConstantArray(c).is_null()
+        && LLVMIsAConstantVector(c).is_null()
+    {
+        return;
+    }
+
+    if !seen.insert(c as usize) {
+        return;
+    }
+
+    for i in 0..LLVMGetNumOperands(c) {
+        collect_exprs(LLVMGetOperand(c, i as u32), opcode, seen, exprs);
+    }
+
+    if is_expr && LLVMGetConstOpcode(c) == opcode {
+        exprs.push(c);
+    }
+}
+
+/// Source element type and address of each constant `getelementptr` kept by markers, keyed by
+/// name of marker.
+pub fn gep_source_types(module: &Module) -> BTreeMap<String, (TypeRef, ConstantRef)> {
+    let mut res = BTreeMap::new();
+
+    for global in &module.global_vars {
+        let name = yul_ident_name(&global.name);
+
+        if !is_gep_marker(&name) {
+            continue;
+        }
+
+        if let Some(Constant::Struct { values, .. }) = global.initializer.as_deref() {
+            if values.len() >= 2 {
+                res.insert(name, (module.types.type_of(&values[0]), values[1].clone()));
+            }
+        }
+    }
+
+    res
+}
+
+/// Whether global is a marker of `mark_const_geps`.
+pub fn is_gep_marker(name: &str) -> bool {
+    name.starts_with(GEP_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use llvm_ir::Module;
+
+    use super::*;
+    use crate::utils::tag_wide_constants;
+
+    #[test]
+    fn test_gep_source_types() {
+        let ir = r#"
+            @arr = global [4 x i64] zeroinitializer
+
+            define void @f() {
+              store i64 1, ptr getelementptr ([4 x i64], ptr @arr, i64 0, i64 1)
+              store i64 2, ptr getelementptr ([2 x i128], ptr @arr, i64 0, i64 1)
+              store i64 3, ptr getelementptr ([4 x i64], ptr @arr, i64 0, i64 0)
+              ret void
+            }
+        "#;
+
+        let (ir, _) = tag_wide_constants(ir).unwrap();
+        let module = Module::from_ir_str(&ir).unwrap();
+
+        let mut types: Vec<_> = gep_source_types(&module)
+            .into_values()
+            .map(|(ty, address)| (ty.to_string(), address.to_string()))
+            .collect();
+        types.sort();
+
+        assert_eq!(
+            types,
+            vec![
+                ("[2 x i128]".to_string(), "ptr @arr".to_string()),
+                ("[4 x i64]".to_string(), "ptr @arr".to_string()),
+            ]
+        );
+    }
+}
@@ -1,128 +1,1375 @@
 use std::collections::{BTreeMap, BTreeSet};
 
 use anyhow::{anyhow, Result};
-use llvm_ir::{terminator::Ret, BasicBlock, Name, Operand, Terminator};
-use yuler::{Assignment, Block, FunctionCall, Ident, Statement};
+use llvm_ir::{
+    terminator::{CondBr, Invoke, Ret, Switch},
+    types::Types,
+    Function, Name, Terminator,
+};
+use llvm_ir_analysis::{CFGNode, FunctionAnalysis};
+use yuler::{
+    Assignment, CaseBlock, ForLoop, FunctionCall, Ident, If, Literal, Statement, Value,
+    VariableDeclare,
+};
 
-use super::{build_list_by_type, yul_ident_name};
+use crate::{error, Config, ConstantFlatter, OperandFlatter, PhiCompiler, TypeFlatter, UnwindMode};
 
-fn _iter_control_block(
-    block_names: &mut BTreeSet<Name>,
-    compiled_blocks: &mut BTreeMap<Name, Block>,
-    origin_blocks: &BTreeMap<Name, &BasicBlock>,
-    name: Name,
-) -> Result<()> {
-    let basic_block = origin_blocks.get(&name).ok_or(anyhow!("Fatel error"))?;
+/// Limit of emitted blocks, times of block count. Blocks are duplicated when they can't be
+/// reached by fallthrough.
+const MAX_DUPLICATION: usize = 4;
 
-    let basic_block_name = basic_block.name.clone();
-    let term = basic_block.term.clone();
+/// State variable of dispatch loop.
+const DISPATCH_STATE: &str = "__state";
 
-    match term {
-        Terminator::Ret(t) => {
-            let generated_block = compiled_blocks
-                .get_mut(&name)
-                .ok_or(anyhow!("Fatel error"))?;
+/// Pending jump out of nested loop.
+const EXIT_STATE: &str = "__exit";
 
-            compile_return_value(&t, generated_block)?
+/// Control flow can't be structured, it's built into a dispatch loop by
+/// `ControlFlowBuilder::build_dispatch` instead.
+#[derive(Debug)]
+pub struct Unstructured(String);
+
+impl std::fmt::Display for Unstructured {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for Unstructured {}
+
+/// Rebuild structured control flow from basic blocks.
+///
+/// Loops become `for {} 1 {} { .. }` with `break` and `continue`, `br` falls through,
+/// `condbr` and `switch` become `if` or `switch` and join at the immediate post dominator.
+///
+/// Jump to header or follow of an outer loop sets `__exit` and breaks, then each enclosing loop
+/// checks `__exit` after its inner loop, until the target loop does `continue` or `break`.
+///
+/// If control flow can't be structured, such as irreducible control flow, `build` returns
+/// `Unstructured` error, and blocks can be built into a dispatch loop instead.
+pub struct ControlFlowBuilder<'a> {
+    llvm_func: &'a Function,
+    types: &'a Types,
+    config: &'a Config,
+    blocks: BTreeMap<Name, Vec<Statement>>,
+
+    ipostdoms: BTreeMap<&'a Name, &'a Name>,
+    /// Header of loops, and the block after loop.
+    loops: BTreeMap<&'a Name, Option<&'a Name>>,
+    irreducible: Option<String>,
+    emitted: usize,
+    /// Codes of pending jumps out of nested loop, see `exit_code`.
+    exits: BTreeSet<u64>,
+    has_exit: bool,
+}
+
+impl<'a> ControlFlowBuilder<'a> {
+    pub fn new(
+        llvm_func: &'a Function,
+        types: &'a Types,
+        config: &'a Config,
+        blocks: BTreeMap<Name, Vec<Statement>>,
+    ) -> Self {
+        let analysis = FunctionAnalysis::new(llvm_func);
+        let cfg = analysis.control_flow_graph();
+        let domtree = analysis.dominator_tree();
+        let postdomtree = analysis.postdominator_tree();
+
+        let entry = cfg.entry();
+
+        let succs = |name: &'a Name| -> Vec<&'a Name> {
+            cfg.succs(name)
+                .filter_map(|n| match n {
+                    CFGNode::Block(b) => Some(b),
+                    CFGNode::Return => None,
+                })
+                .collect()
+        };
+
+        // Find back edges by depth first search, retreating edge to a block which doesn't
+        // dominate the source means irreducible control flow.
+        let mut latches: BTreeMap<&'a Name, Vec<&'a Name>> = BTreeMap::new();
+        let mut irreducible = None;
+        let mut visited = BTreeSet::from([entry]);
+        let mut on_stack = BTreeSet::from([entry]);
+        let mut stack = vec![(entry, succs(entry), 0)];
+
+        while let Some((name, next, index)) = stack.last_mut() {
+            let name = *name;
+
+            if let Some(succ) = next.get(*index).copied() {
+                *index += 1;
+
+                if on_stack.contains(succ) {
+                    if domtree.dominates(CFGNode::Block(succ), CFGNode::Block(name)) {
+                        latches.entry(succ).or_default().push(name);
+                    } else {
+                        irreducible = Some(format!(
+                            "Irreducible control flow in {}: {name} -> {succ}",
+                            llvm_func.name
+                        ));
+                    }
+                } else if visited.insert(succ) {
+                    on_stack.insert(succ);
+                    stack.push((succ, succs(succ), 0));
+                }
+            } else {
+                on_stack.remove(name);
+                stack.pop();
+            }
         }
-        Terminator::Br(t) => {
-            _iter_control_block(
-                block_names,
-                compiled_blocks,
-                origin_blocks,
-                basic_block_name.clone(),
-            )?;
 
-            let generated_block = compiled_blocks.get(&name).ok_or(anyhow!("Fatel error"))?;
+        let mut ipostdoms = BTreeMap::new();
 
-            let block = &compiled_blocks
-                .get(&t.dest)
-                .ok_or(anyhow!("Fatel error: Failed to get block"))?;
+        for name in &visited {
+            if let Some(CFGNode::Block(p)) = postdomtree.ipostdom(name) {
+                ipostdoms.insert(*name, p);
+            }
+        }
+
+        let mut loops = BTreeMap::new();
 
-            let mut res = generated_block.clone();
-            res.0.extend_from_slice(&block.0);
+        for (header, latches) in latches {
+            let mut body = BTreeSet::from([header]);
+            let mut worklist = latches;
 
-            compiled_blocks.insert(basic_block_name, res);
+            while let Some(name) = worklist.pop() {
+                if body.insert(name) {
+                    worklist.extend(cfg.preds(name));
+                }
+            }
+
+            let mut exits = BTreeSet::new();
+
+            for name in &body {
+                for succ in succs(name) {
+                    if !body.contains(succ) {
+                        exits.insert(succ);
+                    }
+                }
+            }
+
+            let follow = match ipostdoms.get(header) {
+                Some(p) if !body.contains(p) => Some(*p),
+                _ if exits.len() == 1 => exits.pop_first(),
+                _ => None,
+            };
+
+            loops.insert(header, follow);
         }
-        Terminator::CondBr(_t) => {}
-        Terminator::Switch(_t) => {}
-        Terminator::Unreachable(_t) => {
-            let generated_block = compiled_blocks
-                .get_mut(&name)
-                .ok_or(anyhow!("Fatel error"))?;
 
-            generated_block
-                .0
-                .push(FunctionCall::new(Ident::new("invalid")?).into());
+        Self {
+            llvm_func,
+            types,
+            config,
+            blocks,
+            ipostdoms,
+            loops,
+            irreducible,
+            emitted: 0,
+            exits: BTreeSet::new(),
+            has_exit: false,
         }
-        _ => return Err(anyhow!("Unsupported terminator")),
     }
 
-    block_names.insert(name);
+    pub fn build(&mut self) -> Result<Vec<Statement>> {
+        if let Some(e) = &self.irreducible {
+            return Err(Unstructured(e.clone()).into());
+        }
+
+        self.emitted = 0;
+        self.exits.clear();
+        self.has_exit = false;
 
-    Ok(())
-}
+        let entry = &self.llvm_func.basic_blocks[0].name;
 
-pub fn build_control_block_from_entry(
-    compiled_blocks: &mut BTreeMap<Name, Block>,
-    origin_blocks: &BTreeMap<Name, &BasicBlock>,
-    entry: Name,
-) -> Result<()> {
-    log::info!("{entry}");
-    let mut block_names = BTreeSet::new();
+        let mut stmts = self.build_region(entry, None, &[])?;
 
-    _iter_control_block(&mut block_names, compiled_blocks, origin_blocks, entry)?;
+        if let Some(Statement::Leave) = stmts.last() {
+            stmts.pop();
+        }
 
-    Ok(())
-}
+        if self.has_exit {
+            stmts.insert(
+                0,
+                VariableDeclare {
+                    names: vec![Ident::new(EXIT_STATE)?],
+                    value: Literal::int_number(0)?.into(),
+                }
+                .into(),
+            );
+        }
+
+        Ok(stmts)
+    }
+
+    /// Build statements from `start`, stop when reach `follow`.
+    fn build_region(
+        &mut self,
+        start: &'a Name,
+        follow: Option<&'a Name>,
+        loops: &[&'a Name],
+    ) -> Result<Vec<Statement>> {
+        let mut stmts = Vec::new();
+        let mut current = Some(start);
+
+        while let Some(name) = current {
+            if Some(name) == follow {
+                break;
+            }
+
+            self.emitted += 1;
+            if self.emitted > MAX_DUPLICATION * self.llvm_func.basic_blocks.len() {
+                return Err(Unstructured(format!(
+                    "Too many duplicated blocks in {}",
+                    self.llvm_func.name
+                ))
+                .into());
+            }
+
+            if self.loops.contains_key(name) && loops.last() != Some(&name) {
+                let mut inner = loops.to_vec();
+                inner.push(name);
+
+                let outer = std::mem::take(&mut self.exits);
+                let mut block = self.build_region(name, None, &inner)?;
+                let exits = std::mem::replace(&mut self.exits, outer);
+
+                if let Some(Statement::Continue) = block.last() {
+                    block.pop();
+                }
+
+                stmts.push(
+                    ForLoop {
+                        init: Default::default(),
+                        cond: Literal::int_number(1)?.into(),
+                        incr: Default::default(),
+                        block: block.into(),
+                    }
+                    .into(),
+                );
+
+                if !exits.is_empty() {
+                    stmts.push(self.build_exit_check(exits, loops)?);
+                }
+
+                // Follow of inner loop may be header or follow of outer loop.
+                current = match self.loops[name] {
+                    Some(next) => {
+                        let (mut res, next) = self.build_goto(next, follow, loops)?;
+                        stmts.append(&mut res);
+
+                        next
+                    }
+                    None => None,
+                };
+                continue;
+            }
+
+            let block = self
+                .blocks
+                .get(name)
+                .ok_or(anyhow!("Fatel: Failed to get basic block: {name}"))?;
+            stmts.extend_from_slice(block);
+
+            current = self.build_terminator(&mut stmts, name, follow, loops)?;
+        }
+
+        Ok(stmts)
+    }
+
+    /// Build terminator of block, return the block to fallthrough.
+    fn build_terminator(
+        &mut self,
+        stmts: &mut Vec<Statement>,
+        name: &'a Name,
+        follow: Option<&'a Name>,
+        loops: &[&'a Name],
+    ) -> Result<Option<&'a Name>> {
+        let bb = self
+            .llvm_func
+            .get_bb_by_name(name)
+            .ok_or(anyhow!("Fatel: Failed to get basic block: {name}"))?;
+
+        match &bb.term {
+            Terminator::Ret(t) => {
+                stmts.append(&mut self.build_return(t)?);
+                stmts.push(Statement::Leave);
+
+                Ok(None)
+            }
+            Terminator::Unreachable(_) => {
+                stmts.push(FunctionCall::new(Ident::new("invalid")?).into());
+
+                Ok(None)
+            }
+            Terminator::Br(t) => {
+                let (mut res, next) = self.build_jump(name, &t.dest, follow, loops)?;
+                stmts.append(&mut res);
+
+                Ok(next)
+            }
+            Terminator::CondBr(t) => self.build_cond_br(stmts, name, t, loops),
+            Terminator::Switch(t) => self.build_switch(stmts, name, t, loops),
+            Terminator::Invoke(t) => {
+                let (mut res, next) = self.build_jump(name, self.invoke_dest(t), follow, loops)?;
+                stmts.append(&mut res);
+
+                Ok(next)
+            }
+            Terminator::Resume(_) => {
+                stmts.push(self.build_resume()?);
+
+                Ok(None)
+            }
+            _ => Err(anyhow!("Unsupported terminator: {}", bb.term)),
+        }
+    }
+
+    fn build_cond_br(
+        &mut self,
+        stmts: &mut Vec<Statement>,
+        name: &'a Name,
+        t: &'a CondBr,
+        loops: &[&'a Name],
+    ) -> Result<Option<&'a Name>> {
+        let flatter = OperandFlatter::new(self.types, self.config);
+        let cond = flatter.flatten_single(&t.condition)?;
+        let not_cond: Value = FunctionCall {
+            name: Ident::new("iszero")?,
+            args: vec![cond.clone()],
+        }
+        .into();
 
-fn compile_return_value(inst: &Ret, block: &mut Block) -> Result<()> {
-    if let Some(operand) = &inst.return_operand {
-        // Match return value
-
-        match operand {
-            Operand::LocalOperand { name, ty } => {
-                let rname = yul_ident_name(name);
-
-                let dists = build_list_by_type(None, ty, true)?;
-
-                if dists.is_empty() {
-                    block.0.push(Statement::Leave);
-                } else {
-                    for (i, dist) in dists.into_iter().enumerate() {
-                        let from = Ident::new(format!("{rname}_{i}"))?;
-
-                        block.0.push(
-                            Assignment {
-                                names: vec![dist],
-                                value: from.into(),
-                            }
-                            .into(),
-                        );
+        let merge = self.merge(name, loops);
+
+        let mut true_block = self.build_branch(name, &t.true_dest, merge, loops)?;
+        let mut false_block = self.build_branch(name, &t.false_dest, merge, loops)?;
+
+        if merge.is_none() {
+            // Both branches never fallthrough, keep the short one in `if`.
+            if true_block.len() <= false_block.len() {
+                stmts.push(
+                    If {
+                        cond,
+                        block: true_block.into(),
+                    }
+                    .into(),
+                );
+                stmts.append(&mut false_block);
+            } else {
+                stmts.push(
+                    If {
+                        cond: not_cond,
+                        block: false_block.into(),
+                    }
+                    .into(),
+                );
+                stmts.append(&mut true_block);
+            }
+        } else if false_block.is_empty() {
+            if !true_block.is_empty() {
+                stmts.push(
+                    If {
+                        cond,
+                        block: true_block.into(),
                     }
+                    .into(),
+                );
+            }
+        } else if true_block.is_empty() {
+            stmts.push(
+                If {
+                    cond: not_cond,
+                    block: false_block.into(),
                 }
-                block.0.push(Statement::Leave)
+                .into(),
+            );
+        } else {
+            stmts.push(
+                yuler::Switch {
+                    cond,
+                    cases: vec![CaseBlock {
+                        cond: Literal::int_number(0)?,
+                        block: false_block.into(),
+                    }],
+                    default: Some(true_block.into()),
+                }
+                .into(),
+            );
+        }
+
+        Ok(merge)
+    }
+
+    fn build_switch(
+        &mut self,
+        stmts: &mut Vec<Statement>,
+        name: &'a Name,
+        t: &'a Switch,
+        loops: &[&'a Name],
+    ) -> Result<Option<&'a Name>> {
+        let flatter = OperandFlatter::new(self.types, self.config);
+        let cond = flatter.flatten_single(&t.operand)?;
+
+        let merge = self.merge(name, loops);
+
+        let constant_flatter = ConstantFlatter::new(self.types, self.config);
+        let mut cases = Vec::new();
+
+        for (constant, dest) in &t.dests {
+            let cond = constant_flatter
+                .flatten(constant)?
+                .first()
+                .and_then(|v| v.as_literal())
+                .cloned()
+                .ok_or(anyhow!("{} switch case: {constant}", error::WRONG_ARG))?;
+
+            cases.push(CaseBlock {
+                cond,
+                block: self.build_branch(name, dest, merge, loops)?.into(),
+            });
+        }
+
+        let default = self.build_branch(name, &t.default_dest, merge, loops)?;
+
+        stmts.push(
+            yuler::Switch {
+                cond,
+                cases,
+                default: Some(default.into()),
             }
-            Operand::ConstantOperand(_) => {
-                todo!()
+            .into(),
+        );
+
+        Ok(merge)
+    }
+
+    fn build_branch(
+        &mut self,
+        from: &'a Name,
+        to: &'a Name,
+        follow: Option<&'a Name>,
+        loops: &[&'a Name],
+    ) -> Result<Vec<Statement>> {
+        let (mut stmts, next) = self.build_jump(from, to, follow, loops)?;
+
+        if let Some(next) = next {
+            stmts.append(&mut self.build_region(next, follow, loops)?);
+        }
+
+        Ok(stmts)
+    }
+
+    /// Build jump from `from` to `to`, return the block which need to be placed inline.
+    fn build_jump(
+        &mut self,
+        from: &'a Name,
+        to: &'a Name,
+        follow: Option<&'a Name>,
+        loops: &[&'a Name],
+    ) -> Result<(Vec<Statement>, Option<&'a Name>)> {
+        let phi_compiler = PhiCompiler::new(self.llvm_func, self.types, self.config);
+        let mut stmts = phi_compiler.compile_edge(from, to)?;
+
+        let (mut res, next) = self.build_goto(to, follow, loops)?;
+        stmts.append(&mut res);
+
+        Ok((stmts, next))
+    }
+
+    /// Build jump to `to` after phi copies, return the block which need to be placed inline.
+    fn build_goto(
+        &mut self,
+        to: &'a Name,
+        follow: Option<&'a Name>,
+        loops: &[&'a Name],
+    ) -> Result<(Vec<Statement>, Option<&'a Name>)> {
+        let mut stmts = Vec::new();
+
+        if let Some(header) = loops.last() {
+            if *header == to {
+                stmts.push(Statement::Continue);
+                return Ok((stmts, None));
+            }
+
+            if self.loops[header] == Some(to) {
+                stmts.push(Statement::Break);
+                return Ok((stmts, None));
             }
-            _ => return Err(anyhow!("Unsupported operand: {}", inst)),
         }
-    } else {
-        block.0.push(Statement::Leave)
+
+        if let Some(header) = loops
+            .iter()
+            .rev()
+            .find(|h| **h == to || self.loops[*h] == Some(to))
+        {
+            let code = self.exit_code(header, *header != to);
+
+            self.exits.insert(code);
+            self.has_exit = true;
+
+            stmts.push(exit_assign(code)?);
+            stmts.push(Statement::Break);
+            return Ok((stmts, None));
+        }
+
+        if Some(to) == follow {
+            Ok((stmts, None))
+        } else {
+            Ok((stmts, Some(to)))
+        }
+    }
+
+    /// Join point of branches. Header and follow of loops must be reached by `continue` and
+    /// `break`, so they can't be used.
+    fn merge(&self, name: &'a Name, loops: &[&'a Name]) -> Option<&'a Name> {
+        let merge = self.ipostdoms.get(name).copied()?;
+
+        if self.is_loop_target(merge, loops) {
+            None
+        } else {
+            Some(merge)
+        }
+    }
+
+    /// Code of jump out of nested loop to header of loop, or to its follow if `is_break`.
+    fn exit_code(&self, header: &'a Name, is_break: bool) -> u64 {
+        let index = self
+            .loops
+            .keys()
+            .position(|h| *h == header)
+            .unwrap_or_default() as u64;
+
+        2 * (index + 1) + u64::from(is_break)
+    }
+
+    /// Check pending jumps after inner loop. Jumps to enclosing loop are done here, others break
+    /// again and stay pending for outer loops.
+    ///
+    /// ```text
+    /// if __exit {
+    ///     if eq(__exit, 2) { __exit := 0 continue }
+    ///     if eq(__exit, 3) { __exit := 0 break }
+    ///     break
+    /// }
+    /// ```
+    fn build_exit_check(&mut self, exits: BTreeSet<u64>, loops: &[&'a Name]) -> Result<Statement> {
+        let header = loops.last().ok_or(anyhow!(
+            "Fatel: Jump out of loop without enclosing loop in {}",
+            self.llvm_func.name
+        ))?;
+
+        let mut block: Vec<Statement> = Vec::new();
+        let mut propagate = false;
+
+        for code in exits {
+            let jump = if code == self.exit_code(header, false) {
+                Statement::Continue
+            } else if code == self.exit_code(header, true) {
+                Statement::Break
+            } else {
+                self.exits.insert(code);
+                propagate = true;
+                continue;
+            };
+
+            let cond = FunctionCall {
+                name: Ident::new("eq")?,
+                args: vec![
+                    Ident::new(EXIT_STATE)?.into(),
+                    Literal::int_number(code)?.into(),
+                ],
+            };
+
+            block.push(
+                If {
+                    cond: cond.into(),
+                    block: vec![exit_assign(0)?, jump].into(),
+                }
+                .into(),
+            );
+        }
+
+        if propagate {
+            block.push(Statement::Break);
+        }
+
+        Ok(If {
+            cond: Ident::new(EXIT_STATE)?.into(),
+            block: block.into(),
+        }
+        .into())
     }
 
-    Ok(())
+    fn is_loop_target(&self, name: &'a Name, loops: &[&'a Name]) -> bool {
+        loops
+            .iter()
+            .any(|header| *header == name || self.loops[header] == Some(name))
+    }
+
+    /// Build blocks into a state machine:
+    ///
+    /// ```text
+    /// let __state := 0
+    /// for {} 1 {} {
+    ///     switch __state
+    ///     case 0 { .. __state := 1 }
+    ///     case 1 { .. leave }
+    /// }
+    /// ```
+    pub fn build_dispatch(&self) -> Result<Vec<Statement>> {
+        let states: BTreeMap<&Name, u64> = self
+            .llvm_func
+            .basic_blocks
+            .iter()
+            .enumerate()
+            .map(|(i, bb)| (&bb.name, i as u64))
+            .collect();
+
+        let mut cases = Vec::new();
+
+        for (i, bb) in self.llvm_func.basic_blocks.iter().enumerate() {
+            let mut block = self
+                .blocks
+                .get(&bb.name)
+                .ok_or(anyhow!("Fatel: Failed to get basic block: {}", bb.name))?
+                .clone();
+
+            block.append(&mut self.build_dispatch_terminator(&bb.name, &bb.term, &states)?);
+
+            cases.push(CaseBlock {
+                cond: Literal::int_number(i as u64)?,
+                block: block.into(),
+            });
+        }
+
+        let state = Ident::new(DISPATCH_STATE)?;
+
+        Ok(vec![
+            VariableDeclare {
+                names: vec![state.clone()],
+                value: Literal::int_number(0)?.into(),
+            }
+            .into(),
+            ForLoop {
+                init: Default::default(),
+                cond: Literal::int_number(1)?.into(),
+                incr: Default::default(),
+                block: vec![yuler::Switch {
+                    cond: state.into(),
+                    cases,
+                    default: None,
+                }
+                .into()]
+                .into(),
+            }
+            .into(),
+        ])
+    }
+
+    fn build_dispatch_terminator(
+        &self,
+        name: &Name,
+        term: &Terminator,
+        states: &BTreeMap<&Name, u64>,
+    ) -> Result<Vec<Statement>> {
+        let flatter = OperandFlatter::new(self.types, self.config);
+
+        let mut res = Vec::new();
+
+        match term {
+            Terminator::Ret(t) => {
+                res.append(&mut self.build_return(t)?);
+                res.push(Statement::Leave);
+            }
+            Terminator::Unreachable(_) => {
+                res.push(FunctionCall::new(Ident::new("invalid")?).into());
+            }
+            Terminator::Br(t) => {
+                res.append(&mut self.build_state_jump(name, &t.dest, states)?);
+            }
+            Terminator::CondBr(t) => {
+                res.push(
+                    yuler::Switch {
+                        cond: flatter.flatten_single(&t.condition)?,
+                        cases: vec![CaseBlock {
+                            cond: Literal::int_number(0)?,
+                            block: self.build_state_jump(name, &t.false_dest, states)?.into(),
+                        }],
+                        default: Some(self.build_state_jump(name, &t.true_dest, states)?.into()),
+                    }
+                    .into(),
+                );
+            }
+            Terminator::Switch(t) => {
+                let constant_flatter = ConstantFlatter::new(self.types, self.config);
+                let mut cases = Vec::new();
+
+                for (constant, dest) in &t.dests {
+                    let cond = constant_flatter
+                        .flatten(constant)?
+                        .first()
+                        .and_then(|v| v.as_literal())
+                        .cloned()
+                        .ok_or(anyhow!("{} switch case: {constant}", error::WRONG_ARG))?;
+
+                    cases.push(CaseBlock {
+                        cond,
+                        block: self.build_state_jump(name, dest, states)?.into(),
+                    });
+                }
+
+                res.push(
+                    yuler::Switch {
+                        cond: flatter.flatten_single(&t.operand)?,
+                        cases,
+                        default: Some(self.build_state_jump(name, &t.default_dest, states)?.into()),
+                    }
+                    .into(),
+                );
+            }
+            Terminator::Invoke(t) => {
+                res.append(&mut self.build_state_jump(name, self.invoke_dest(t), states)?);
+            }
+            Terminator::Resume(_) => {
+                res.push(self.build_resume()?);
+            }
+            _ => return Err(anyhow!("Unsupported terminator: {}", term)),
+        }
+
+        Ok(res)
+    }
+
+    fn build_state_jump(
+        &self,
+        from: &Name,
+        to: &Name,
+        states: &BTreeMap<&Name, u64>,
+    ) -> Result<Vec<Statement>> {
+        let phi_compiler = PhiCompiler::new(self.llvm_func, self.types, self.config);
+        let mut res = phi_compiler.compile_edge(from, to)?;
+
+        let state = states
+            .get(to)
+            .ok_or(anyhow!("Fatel: Failed to get basic block: {to}"))?;
+
+        res.push(
+            Assignment {
+                names: vec![Ident::new(DISPATCH_STATE)?],
+                value: Literal::int_number(*state)?.into(),
+            }
+            .into(),
+        );
+
+        Ok(res)
+    }
+
+    /// Destination of `invoke` after its call is done.
+    ///
+    /// Callee never returns normally if `invoke` returns to `unreachable`. With
+    /// `UnwindMode::Cleanup` it unwinds to landing pad instead, so cleanup runs before revert.
+    fn invoke_dest(&self, t: &'a Invoke) -> &'a Name {
+        let unreachable = self
+            .llvm_func
+            .get_bb_by_name(&t.return_label)
+            .is_some_and(|bb| {
+                bb.instrs.is_empty() && matches!(bb.term, Terminator::Unreachable(_))
+            });
+
+        if unreachable && self.config.unwind == UnwindMode::Cleanup {
+            &t.exception_label
+        } else {
+            &t.return_label
+        }
+    }
+
+    /// `resume` reverts after cleanup, it's unreachable unless cleanup is enabled.
+    fn build_resume(&self) -> Result<Statement> {
+        let stmt = match self.config.unwind {
+            UnwindMode::Abort => FunctionCall::new(Ident::new("invalid")?),
+            UnwindMode::Cleanup => FunctionCall {
+                name: Ident::new("revert")?,
+                args: vec![
+                    Literal::int_number(0)?.into(),
+                    Literal::int_number(0)?.into(),
+                ],
+            },
+        };
+
+        Ok(stmt.into())
+    }
+
+    fn build_return(&self, t: &Ret) -> Result<Vec<Statement>> {
+        let mut res = Vec::new();
+
+        if let Some(operand) = &t.return_operand {
+            let type_flatter = TypeFlatter::new(self.types, self.config);
+            let rets = type_flatter.flatten_return_type(&self.llvm_func.return_type)?;
+
+            let operand_flatter = OperandFlatter::new(self.types, self.config);
+            let values = operand_flatter.flatten(operand)?;
+
+            if rets.len() != values.len() {
+                return Err(anyhow!("{} ret: {}", error::WRONG_ARG, t));
+            }
+
+            for (name, value) in rets.into_iter().zip(values) {
+                res.push(
+                    Assignment {
+                        names: vec![name],
+                        value,
+                    }
+                    .into(),
+                );
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+fn exit_assign(code: u64) -> Result<Statement> {
+    Ok(Assignment {
+        names: vec![Ident::new(EXIT_STATE)?],
+        value: Literal::int_number(code)?.into(),
+    }
+    .into())
 }
 
-// fn compile_cond_br(block_name: Name, br: CondBr) -> Result<()> {
-//     // Check loop or if
-//
-//     if br.true_dest == block_name || br.false_dest == block_name {
-//         // Loop
-//     } else {
-//         // False
-//     }
-//
-//     Ok(())
-// }
+/// Move all variable declarations to the beginning, so variables can be used across blocks.
+pub fn hoist_declares(stmts: Vec<Statement>) -> Result<Vec<Statement>> {
+    let mut names = Vec::new();
+
+    let mut stmts = stmts;
+    _hoist_declares(&mut names, &mut stmts);
+
+    let mut res = Vec::new();
+    let mut seen = BTreeSet::new();
+
+    for name in names {
+        if seen.insert(name.0.clone()) {
+            res.push(
+                VariableDeclare {
+                    names: vec![name],
+                    value: Literal::int_number(0)?.into(),
+                }
+                .into(),
+            );
+        }
+    }
+
+    res.append(&mut stmts);
+
+    Ok(res)
+}
+
+fn _hoist_declares(names: &mut Vec<Ident>, stmts: &mut [Statement]) {
+    for stmt in stmts {
+        match stmt {
+            Statement::VariableDeclare(v) => {
+                names.extend_from_slice(&v.names);
+
+                *stmt = Assignment {
+                    names: v.names.clone(),
+                    value: v.value.clone(),
+                }
+                .into();
+            }
+            Statement::If(v) => _hoist_declares(names, &mut v.block.0),
+            Statement::Switch(v) => {
+                for case in &mut v.cases {
+                    _hoist_declares(names, &mut case.block.0);
+                }
+
+                if let Some(default) = &mut v.default {
+                    _hoist_declares(names, &mut default.0);
+                }
+            }
+            Statement::ForLoop(v) => _hoist_declares(names, &mut v.block.0),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yuler::Writer;
+
+    use crate::{Compiler, Config, UnwindMode};
+
+    /// Compile IR into Yul, lines are trimmed to compare shape without indent.
+    fn compile(ir: &str, entry: &str) -> String {
+        compile_with(Config::default(), ir, entry)
+    }
+
+    fn compile_with(config: Config, ir: &str, entry: &str) -> String {
+        let object = Compiler::new(config)
+            .compile_object_from_ir(ir, entry)
+            .unwrap();
+
+        let mut w = Writer::new(Vec::new(), "    ");
+        object.write(&mut w).unwrap();
+
+        String::from_utf8(w.w)
+            .unwrap()
+            .lines()
+            .map(str::trim)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_if_else() {
+        let yul = compile(
+            r#"
+            define i64 @f(i64 %a) {
+            entry:
+              %c = icmp ult i64 %a, 10
+              br i1 %c, label %then, label %else
+            then:
+              %x = add i64 %a, 1
+              br label %join
+            else:
+              %y = sub i64 %a, 1
+              br label %join
+            join:
+              %r = phi i64 [ %x, %then ], [ %y, %else ]
+              %s = mul i64 %r, 2
+              ret i64 %s
+            }
+            "#,
+            "f",
+        );
+
+        assert!(
+            yul.contains(
+                "c := lt(a, 10)\n\
+                 switch c\n\
+                 case 0 {\n\
+                 y := and(sub(a, 1), 0xffffffffffffffff)\n\
+                 r := y\n\
+                 }\n\
+                 default {\n\
+                 x := and(add(a, 1), 0xffffffffffffffff)\n\
+                 r := x\n\
+                 }\n\
+                 s := and(mul(r, 2), 0xffffffffffffffff)\n\
+                 __yn_return := s\n\
+                 }"
+            ),
+            "{yul}"
+        );
+    }
+
+    #[test]
+    fn test_switch() {
+        let yul = compile(
+            r#"
+            define i64 @f(i64 %a) {
+            entry:
+              switch i64 %a, label %def [ i64 1, label %one
+                                          i64 2, label %two
+                                          i64 3, label %two ]
+            one:
+              br label %join
+            two:
+              br label %join
+            def:
+              br label %join
+            join:
+              %r = phi i64 [ 10, %one ], [ 20, %two ], [ 30, %def ]
+              %s = add i64 %r, %a
+              ret i64 %s
+            }
+            "#,
+            "f",
+        );
+
+        assert!(
+            yul.contains(
+                "switch a\n\
+                 case 1 {\n\
+                 r := 10\n\
+                 }\n\
+                 case 2 {\n\
+                 r := 20\n\
+                 }\n\
+                 case 3 {\n\
+                 r := 20\n\
+                 }\n\
+                 default {\n\
+                 r := 30\n\
+                 }\n\
+                 s := and(add(r, a), 0xffffffffffffffff)\n\
+                 __yn_return := s\n\
+                 }"
+            ),
+            "{yul}"
+        );
+    }
+
+    #[test]
+    fn test_loop_continue() {
+        let yul = compile(
+            r#"
+            define i64 @f(i64 %n) {
+            entry:
+              br label %loop
+            loop:
+              %i = phi i64 [ 0, %entry ], [ %i1, %skip ], [ %i1, %body ]
+              %s = phi i64 [ 0, %entry ], [ %s, %skip ], [ %s1, %body ]
+              %i1 = add i64 %i, 1
+              %done = icmp uge i64 %i, %n
+              br i1 %done, label %exit, label %check
+            check:
+              %odd = and i64 %i, 1
+              %isodd = icmp ne i64 %odd, 0
+              br i1 %isodd, label %skip, label %body
+            skip:
+              br label %loop
+            body:
+              %s1 = add i64 %s, %i
+              br label %loop
+            exit:
+              ret i64 %s
+            }
+            "#,
+            "f",
+        );
+
+        assert!(
+            yul.contains(
+                "for {  } 1 {  } {\n\
+                 i1 := and(add(i, 1), 0xffffffffffffffff)\n\
+                 done := iszero(lt(i, n))\n\
+                 if done {\n\
+                 break\n\
+                 }\n\
+                 odd := and(i, 1)\n\
+                 isodd := iszero(eq(odd, 0))\n\
+                 if isodd {\n\
+                 i := i1\n\
+                 continue\n\
+                 }\n\
+                 s1 := and(add(s, i), 0xffffffffffffffff)\n\
+                 i := i1\n\
+                 s := s1\n\
+                 }\n\
+                 __yn_return := s\n\
+                 }"
+            ),
+            "{yul}"
+        );
+    }
+
+    #[test]
+    fn test_nested_loop_break() {
+        let yul = compile(
+            r#"
+            define i64 @f(i64 %n) {
+            entry:
+              br label %outer
+            outer:
+              %i = phi i64 [ 0, %entry ], [ %i1, %outer.latch ]
+              %oc = icmp ult i64 %i, %n
+              br i1 %oc, label %inner, label %done
+            inner:
+              %j = phi i64 [ 0, %outer ], [ %j1, %inner.latch ]
+              %jc = icmp ult i64 %j, %n
+              br i1 %jc, label %body, label %outer.latch
+            body:
+              %s = add i64 %i, %j
+              %hit = icmp eq i64 %s, 5
+              br i1 %hit, label %done, label %inner.latch
+            inner.latch:
+              %j1 = add i64 %j, 1
+              br label %inner
+            outer.latch:
+              %i1 = add i64 %i, 1
+              br label %outer
+            done:
+              %r = phi i64 [ %i, %outer ], [ %j, %body ]
+              ret i64 %r
+            }
+            "#,
+            "f",
+        );
+
+        // `break` of inner loop goes on to break outer loop, `continue` of outer loop is
+        // pending in `__exit`.
+        assert!(
+            yul.contains(
+                "j := 0\n\
+                 for {  } 1 {  } {\n\
+                 jc := lt(j, n)\n\
+                 if iszero(jc) {\n\
+                 i1 := and(add(i, 1), 0xffffffffffffffff)\n\
+                 i := i1\n\
+                 __exit := 4\n\
+                 break\n\
+                 }\n\
+                 s := and(add(i, j), 0xffffffffffffffff)\n\
+                 hit := eq(s, 5)\n\
+                 if hit {\n\
+                 r := j\n\
+                 break\n\
+                 }\n\
+                 j1 := and(add(j, 1), 0xffffffffffffffff)\n\
+                 j := j1\n\
+                 }\n\
+                 if __exit {\n\
+                 if eq(__exit, 4) {\n\
+                 __exit := 0\n\
+                 continue\n\
+                 }\n\
+                 }\n\
+                 break\n\
+                 }\n\
+                 __yn_return := r\n\
+                 }"
+            ),
+            "{yul}"
+        );
+    }
+
+    #[test]
+    fn test_inner_loop_follow_outer_header() {
+        let yul = compile(
+            r#"
+            define i64 @f(i64 %n) {
+            entry:
+              br label %outer
+            outer:
+              %i = phi i64 [ 0, %entry ], [ %i1, %inner ], [ %i1, %outer.latch ]
+              %c = phi i64 [ 0, %entry ], [ %c1, %inner ], [ %c2, %outer.latch ]
+              %i1 = add i64 %i, 1
+              %od = icmp uge i64 %i, %n
+              br i1 %od, label %exit, label %inner
+            inner:
+              %j = phi i64 [ 0, %outer ], [ %j1, %inner.latch ]
+              %c2 = phi i64 [ %c, %outer ], [ %c1, %inner.latch ]
+              %c1 = add i64 %c2, 1
+              %j1 = add i64 %j, 1
+              %hit = icmp eq i64 %j, %i
+              br i1 %hit, label %outer, label %inner.latch
+            inner.latch:
+              %jd = icmp uge i64 %j1, %n
+              br i1 %jd, label %outer.latch, label %inner
+            outer.latch:
+              br label %outer
+            exit:
+              ret i64 %c
+            }
+            "#,
+            "f",
+        );
+
+        // Header of outer loop is reached by `break` of inner loop, then outer loop goes on.
+        assert!(
+            yul.contains(
+                "if hit {\n\
+                 i := i1\n\
+                 c := c1\n\
+                 break\n\
+                 }\n\
+                 jd := iszero(lt(j1, n))\n\
+                 if jd {\n\
+                 i := i1\n\
+                 c := c2\n\
+                 break\n\
+                 }\n\
+                 j := j1\n\
+                 c2 := c1\n\
+                 }\n\
+                 }\n\
+                 __yn_return := c\n\
+                 }"
+            ),
+            "{yul}"
+        );
+    }
+
+    #[test]
+    fn test_loop_exits() {
+        let yul = compile(
+            r#"
+            define i64 @f(i64 %n, i64 %k) {
+            entry:
+              br label %loop
+            loop:
+              %i = phi i64 [ 0, %entry ], [ %i1, %latch ]
+              %a = icmp eq i64 %i, %k
+              br i1 %a, label %found, label %next
+            next:
+              %b = icmp uge i64 %i, %n
+              br i1 %b, label %miss, label %latch
+            latch:
+              %i1 = add i64 %i, 1
+              br label %loop
+            found:
+              %r = add i64 %i, 100
+              ret i64 %r
+            miss:
+              ret i64 0
+            }
+            "#,
+            "f",
+        );
+
+        assert!(
+            yul.contains(
+                "for {  } 1 {  } {\n\
+                 a := eq(i, k)\n\
+                 if a {\n\
+                 r := and(add(i, 100), 0xffffffffffffffff)\n\
+                 __yn_return := r\n\
+                 leave\n\
+                 }\n\
+                 b := iszero(lt(i, n))\n\
+                 if b {\n\
+                 __yn_return := 0\n\
+                 leave\n\
+                 }\n\
+                 i1 := and(add(i, 1), 0xffffffffffffffff)\n\
+                 i := i1\n\
+                 }\n\
+                 }"
+            ),
+            "{yul}"
+        );
+    }
+
+    #[test]
+    fn test_irreducible() {
+        let yul = compile(
+            r#"
+            define i64 @f(i1 %c, i64 %n) {
+            entry:
+              br i1 %c, label %a, label %b
+            a:
+              %x = phi i64 [ 0, %entry ], [ %y1, %b ]
+              %x1 = add i64 %x, 1
+              %ca = icmp ult i64 %x1, %n
+              br i1 %ca, label %b, label %exit
+            b:
+              %y = phi i64 [ 1, %entry ], [ %x1, %a ]
+              %y1 = add i64 %y, 2
+              %cb = icmp ult i64 %y1, %n
+              br i1 %cb, label %a, label %exit
+            exit:
+              %r = phi i64 [ %x1, %a ], [ %y1, %b ]
+              ret i64 %r
+            }
+            "#,
+            "f",
+        );
+
+        assert!(
+            yul.contains(
+                "__state := 0\n\
+                 for {  } 1 {  } {\n\
+                 switch __state\n\
+                 case 0 {\n\
+                 switch c\n\
+                 case 0 {\n\
+                 y := 1\n\
+                 __state := 2\n\
+                 }\n\
+                 default {\n\
+                 x := 0\n\
+                 __state := 1\n\
+                 }\n\
+                 }"
+            ),
+            "{yul}"
+        );
+        assert!(
+            yul.contains("case 3 {\n__yn_return := r\nleave\n}"),
+            "{yul}"
+        );
+    }
+
+    #[test]
+    fn test_error_without_fallback() {
+        let err = Compiler::new(Config::default())
+            .compile_object_from_ir(
+                r#"
+                @ext = external global i64
+
+                define i64 @f(i1 %c) {
+                entry:
+                  br i1 %c, label %a, label %b
+                a:
+                  ret i64 ptrtoint (ptr @ext to i64)
+                b:
+                  ret i64 0
+                }
+                "#,
+                "f",
+            )
+            .unwrap_err();
+
+        assert!(
+            err.to_string().contains("global without address: ext"),
+            "{err}"
+        );
+    }
+
+    const INVOKE_IR: &str = r#"
+        @flag = global i64 0
+
+        declare i32 @__gxx_personality_v0(...)
+
+        define void @g() {
+          ret void
+        }
+
+        define void @f() personality ptr @__gxx_personality_v0 {
+        entry:
+          invoke void @g() to label %unr unwind label %lp
+        unr:
+          unreachable
+        lp:
+          %l = landingpad { ptr, i32 } cleanup
+          store i64 7, ptr @flag
+          resume { ptr, i32 } %l
+        }
+    "#;
+
+    #[test]
+    fn test_invoke_abort() {
+        let yul = compile(INVOKE_IR, "f");
+
+        assert!(
+            yul.contains(
+                "function f() {\n\
+                 g()\n\
+                 invalid()\n\
+                 }"
+            ),
+            "{yul}"
+        );
+    }
+
+    #[test]
+    fn test_invoke_cleanup() {
+        let yul = compile_with(
+            Config::default().unwind(UnwindMode::Cleanup),
+            INVOKE_IR,
+            "f",
+        );
+
+        assert!(
+            yul.contains(
+                "g()\n\
+                 l_0 := 0\n\
+                 l_1 := 0\n\
+                 mstore(128, 7)\n\
+                 revert(0, 0)\n\
+                 }"
+            ),
+            "{yul}"
+        );
+    }
+}
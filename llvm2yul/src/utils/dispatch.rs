@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+use llvm_ir::Module;
+use yuler::{
+    Assignment, CaseBlock, FunctionCall, FunctionDefinition, Ident, Literal, Statement, Switch,
+    Value,
+};
+
+use super::builtin_call;
+
+pub const DISPATCH_PREFIX: &str = "__yn_dispatch_";
+
+/// Function which can be called by dispatcher.
+#[derive(Debug, Clone)]
+pub struct DispatchTarget {
+    pub id: u64,
+    pub name: Ident,
+    pub args: usize,
+    pub rets: usize,
+}
+
+/// IDs of functions defined in module, used as value of function pointer.
+///
+/// ID starts from `1`, so null pointer never matches a function.
+pub fn function_ids(module: &Module) -> BTreeMap<String, u64> {
+    module
+        .functions
+        .iter()
+        .enumerate()
+        .map(|(i, f)| (f.name.clone(), i as u64 + 1))
+        .collect()
+}
+
+/// Name of dispatcher for indirect call with flattened `args` and `rets`.
+pub fn dispatcher_name(args: usize, rets: usize) -> String {
+    format!("{DISPATCH_PREFIX}{args}_{rets}")
+}
+
+/// Flattened `args` and `rets` of dispatcher, `None` if it isn't dispatcher.
+pub fn dispatcher_arity(name: &str) -> Option<(usize, usize)> {
+    let (args, rets) = name.strip_prefix(DISPATCH_PREFIX)?.split_once('_')?;
+
+    Some((args.parse().ok()?, rets.parse().ok()?))
+}
+
+/// Build dispatcher which calls target of same arity by ID. Unknown ID reverts.
+///
+/// ```text
+/// function __yn_dispatch_1_1(id, a_0) -> r_0 {
+///     switch id
+///     case 1 { r_0 := f(a_0) }
+///     default { revert(0, 0) }
+/// }
+/// ```
+pub fn build_dispatcher(name: &str, targets: &[DispatchTarget]) -> Result<FunctionDefinition> {
+    let (num_args, num_rets) =
+        dispatcher_arity(name).ok_or(anyhow!("Unknown dispatcher: {name}"))?;
+
+    let mut func = FunctionDefinition::new(Ident::new(name)?);
+
+    func.args.push(Ident::new("id")?);
+
+    let mut args = Vec::new();
+    for i in 0..num_args {
+        let arg = Ident::new(format!("a_{i}"))?;
+
+        func.args.push(arg.clone());
+        args.push(Value::from(arg));
+    }
+
+    for i in 0..num_rets {
+        func.rets.push(Ident::new(format!("r_{i}"))?);
+    }
+
+    let mut cases = Vec::new();
+
+    for target in targets {
+        if target.args != num_args || target.rets != num_rets {
+            continue;
+        }
+
+        let call = FunctionCall {
+            name: target.name.clone(),
+            args: args.clone(),
+        };
+
+        let stmt: Statement = if num_rets == 0 {
+            call.into()
+        } else {
+            Assignment {
+                names: func.rets.clone(),
+                value: call.into(),
+            }
+            .into()
+        };
+
+        cases.push(CaseBlock {
+            cond: Literal::int_number(target.id)?,
+            block: vec![stmt].into(),
+        });
+    }
+
+    let zero: Value = Literal::int_number(0)?.into();
+    let revert = builtin_call("revert", vec![zero.clone(), zero])?;
+
+    func.block = vec![Switch {
+        cond: Ident::new("id")?.into(),
+        cases,
+        default: Some(vec![revert.into()].into()),
+    }
+    .into()]
+    .into();
+
+    Ok(func)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{dispatcher_arity, dispatcher_name};
+
+    #[test]
+    fn test_dispatcher_arity() {
+        assert_eq!(dispatcher_arity(&dispatcher_name(2, 1)), Some((2, 1)));
+        assert_eq!(dispatcher_arity("__yn_dispatch_3"), None);
+        assert_eq!(dispatcher_arity("__yn_memcpy"), None);
+    }
+}
@@ -0,0 +1,457 @@
+use std::{collections::BTreeSet, iter};
+
+use anyhow::Result;
+use llvm_ir::{Constant, ConstantRef, Instruction, Module, Operand, Terminator, Type};
+use yuler::{
+    Assignment, Data, FunctionCall, FunctionDefinition, HexLiteral, Ident, If, Literal, Statement,
+    Value,
+};
+
+use super::{builtin_call, yul_ident_name};
+use crate::binary_constant;
+
+pub const GLOBAL_PREFIX: &str = "__yn_global_";
+pub const STATICS_DATA: &str = "__yn_statics";
+
+/// Constant global which is placed into data section.
+#[derive(Debug, Clone)]
+pub struct GlobalData {
+    pub name: String,
+    pub bytes: Vec<u8>,
+    /// Words which are only known at runtime, like address of other global.
+    pub relocations: Vec<(u64, Value)>,
+}
+
+/// Name of function which loads global into memory.
+pub fn global_loader_name(name: &str) -> String {
+    format!("{GLOBAL_PREFIX}{name}")
+}
+
+/// Name of global loaded by function, `None` if it isn't loader.
+pub fn loader_global(name: &str) -> Option<&str> {
+    name.strip_prefix(GLOBAL_PREFIX)
+}
+
+/// Call of loader, which is address of global in memory.
+pub fn global_address(name: &str) -> Result<Value> {
+    Ok(FunctionCall::new(Ident::new(global_loader_name(name))?).into())
+}
+
+/// Bytes of `[N x i8]` constant, trailing NUL of C string is removed.
+pub fn const_byte_string(constant: &Constant) -> Option<Vec<u8>> {
+    let elements = match constant {
+        Constant::Array {
+            element_type,
+            elements,
+        } if matches!(element_type.as_ref(), Type::IntegerType { bits: 8 }) => elements,
+        _ => return None,
+    };
+
+    let mut bytes = elements
+        .iter()
+        .map(|e| match e.as_ref() {
+            Constant::Int { bits: 8, value } => Some(*value as u8),
+            _ => None,
+        })
+        .collect::<Option<Vec<u8>>>()?;
+
+    if bytes.last() == Some(&0) {
+        bytes.pop();
+    }
+
+    Some(bytes)
+}
+
+/// Build loader which copies data of global into memory at first call, then fills relocations.
+///
+/// Address is cached in `slot`, so later calls return the same address. Memory is taken from free
+/// memory pointer at `0x40`. Slot is set before relocations, so globals can refer to each other.
+///
+/// ```text
+/// function __yn_global_msg() -> p {
+///     p := mload(384)
+///     if iszero(p) {
+///         p := mload(0x40)
+///         mstore(0x40, add(p, 64))
+///         mstore(384, p)
+///         datacopy(p, dataoffset("msg"), 64)
+///         mstore(add(p, 32), __yn_global_other())
+///     }
+/// }
+/// ```
+pub fn build_global_loader(data: &GlobalData, slot: u64) -> Result<FunctionDefinition> {
+    let mut func = FunctionDefinition::new(Ident::new(global_loader_name(&data.name))?);
+    func.rets.push(Ident::new("p")?);
+
+    let p: Value = Ident::new("p")?.into();
+    let slot: Value = Literal::int_number(slot)?.into();
+    let free: Value = Literal::hex_number("0x40")?.into();
+
+    let size = data.bytes.len() as u64;
+    let words = Literal::int_number(size.div_ceil(32) * 32)?.into();
+
+    let assign = |value: FunctionCall| -> Statement {
+        Assignment {
+            names: func.rets.clone(),
+            value: value.into(),
+        }
+        .into()
+    };
+
+    let mut load = vec![
+        assign(builtin_call("mload", vec![free.clone()])?),
+        builtin_call(
+            "mstore",
+            vec![free, builtin_call("add", vec![p.clone(), words])?.into()],
+        )?
+        .into(),
+        builtin_call("mstore", vec![slot.clone(), p.clone()])?.into(),
+    ];
+
+    if !data.bytes.is_empty() {
+        let offset = builtin_call("dataoffset", vec![Literal::ascii(&data.name)?.into()])?;
+        let size = Literal::int_number(size)?.into();
+
+        load.push(builtin_call("datacopy", vec![p.clone(), offset.into(), size])?.into());
+    }
+
+    for (offset, value) in &data.relocations {
+        let address = if *offset == 0 {
+            p.clone()
+        } else {
+            builtin_call("add", vec![p.clone(), Literal::int_number(*offset)?.into()])?.into()
+        };
+
+        load.push(builtin_call("mstore", vec![address, value.clone()])?.into());
+    }
+
+    func.block = vec![
+        assign(builtin_call("mload", vec![slot])?),
+        If {
+            cond: builtin_call("iszero", vec![p])?.into(),
+            block: load.into(),
+        }
+        .into(),
+    ]
+    .into();
+
+    Ok(func)
+}
+
+/// Data section of global, `None` if it is empty.
+pub fn build_data_section(data: &GlobalData) -> Result<Option<Data>> {
+    if data.bytes.is_empty() {
+        return Ok(None);
+    }
+
+    let hex: String = data.bytes.iter().map(|b| format!("{b:02x}")).collect();
+
+    Ok(Some(Data {
+        name: Ident::new(&data.name)?,
+        data: HexLiteral::new(hex)?,
+    }))
+}
+
+/// Build prologue of entry which initializes mutable globals in region from `base` to `end`,
+/// then moves free memory pointer after region.
+///
+/// Trailing zeros of data are removed, since memory is zero at beginning.
+///
+/// ```text
+/// mstore(0x40, 192)
+/// datacopy(128, dataoffset("__yn_statics"), 33)
+/// mstore(160, __yn_global_msg())
+/// ```
+pub fn build_statics_prologue(
+    base: u64,
+    end: u64,
+    data: &mut GlobalData,
+) -> Result<Vec<Statement>> {
+    let num = |n: u64| -> Result<Value> { Ok(Literal::int_number(n)?.into()) };
+
+    let len = data
+        .bytes
+        .iter()
+        .rposition(|b| *b != 0)
+        .map_or(0, |i| i + 1);
+    data.bytes.truncate(len);
+
+    let mut stmts: Vec<Statement> = vec![builtin_call(
+        "mstore",
+        vec![Literal::hex_number("0x40")?.into(), num(end)?],
+    )?
+    .into()];
+
+    if !data.bytes.is_empty() {
+        let offset = builtin_call("dataoffset", vec![Literal::ascii(&data.name)?.into()])?;
+
+        stmts.push(
+            builtin_call(
+                "datacopy",
+                vec![num(base)?, offset.into(), num(data.bytes.len() as u64)?],
+            )?
+            .into(),
+        );
+    }
+
+    for (offset, value) in &data.relocations {
+        stmts.push(builtin_call("mstore", vec![num(base + offset)?, value.clone()])?.into());
+    }
+
+    Ok(stmts)
+}
+
+/// Collect names of functions called in statements.
+pub fn called_functions(stmts: &[Statement], names: &mut BTreeSet<String>) {
+    for stmt in stmts {
+        match stmt {
+            Statement::VariableDeclare(v) => value_calls(&v.value, names),
+            Statement::Assignment(a) => value_calls(&a.value, names),
+            Statement::FunctionCall(c) => call_functions(c, names),
+            Statement::If(i) => {
+                value_calls(&i.cond, names);
+                called_functions(&i.block.0, names);
+            }
+            Statement::Switch(s) => {
+                value_calls(&s.cond, names);
+
+                for c in &s.cases {
+                    called_functions(&c.block.0, names);
+                }
+
+                if let Some(d) = &s.default {
+                    called_functions(&d.0, names);
+                }
+            }
+            Statement::ForLoop(f) => {
+                called_functions(&f.init.0, names);
+                value_calls(&f.cond, names);
+                called_functions(&f.incr.0, names);
+                called_functions(&f.block.0, names);
+            }
+            Statement::FunctionDefinition(f) => called_functions(&f.block.0, names),
+            Statement::Break | Statement::Continue | Statement::Leave => {}
+        }
+    }
+}
+
+/// Collect names of functions called in value.
+pub fn value_calls(value: &Value, names: &mut BTreeSet<String>) {
+    if let Value::FunctionCall(c) = value {
+        call_functions(c, names);
+    }
+}
+
+fn call_functions(call: &FunctionCall, names: &mut BTreeSet<String>) {
+    names.insert(call.name.0.clone());
+
+    for a in &call.args {
+        value_calls(a, names);
+    }
+}
+
+/// Names of globals referenced by functions of module which are `compiled`, and by initializers
+/// of referenced globals.
+pub fn used_globals(module: &Module, compiled: impl Fn(&str) -> bool) -> BTreeSet<String> {
+    let mut names = BTreeSet::new();
+
+    for func in module.functions.iter().filter(|f| compiled(&f.name)) {
+        for bb in &func.basic_blocks {
+            for inst in &bb.instrs {
+                for operand in instruction_operands(inst) {
+                    operand_globals(operand, &mut names);
+                }
+            }
+
+            for operand in terminator_operands(&bb.term) {
+                operand_globals(operand, &mut names);
+            }
+        }
+    }
+
+    let mut pending: Vec<String> = names.iter().cloned().collect();
+
+    while let Some(name) = pending.pop() {
+        let initializer = module
+            .global_vars
+            .iter()
+            .find(|g| yul_ident_name(&g.name) == name)
+            .and_then(|g| g.initializer.as_ref());
+
+        let mut refs = BTreeSet::new();
+        if let Some(initializer) = initializer {
+            constant_globals(initializer, &mut refs);
+        }
+
+        for r in refs {
+            if names.insert(r.clone()) {
+                pending.push(r);
+            }
+        }
+    }
+
+    names
+}
+
+fn instruction_operands(inst: &Instruction) -> Vec<&Operand> {
+    match inst {
+        Instruction::Add(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Sub(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Mul(i) => vec![&i.operand0, &i.operand1],
+        Instruction::UDiv(i) => vec![&i.operand0, &i.operand1],
+        Instruction::SDiv(i) => vec![&i.operand0, &i.operand1],
+        Instruction::URem(i) => vec![&i.operand0, &i.operand1],
+        Instruction::SRem(i) => vec![&i.operand0, &i.operand1],
+        Instruction::And(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Or(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Xor(i) => vec![&i.operand0, &i.operand1],
+        Instruction::Shl(i) => vec![&i.operand0, &i.operand1],
+        Instruction::LShr(i) => vec![&i.operand0, &i.operand1],
+        Instruction::AShr(i) => vec![&i.operand0, &i.operand1],
+        Instruction::ICmp(i) => vec![&i.operand0, &i.operand1],
+        Instruction::ExtractValue(i) => vec![&i.aggregate],
+        Instruction::InsertValue(i) => vec![&i.aggregate, &i.element],
+        Instruction::Alloca(i) => vec![&i.num_elements],
+        Instruction::Load(i) => vec![&i.address],
+        Instruction::Store(i) => vec![&i.address, &i.value],
+        Instruction::GetElementPtr(i) => iter::once(&i.address).chain(&i.indices).collect(),
+        Instruction::Trunc(i) => vec![&i.operand],
+        Instruction::ZExt(i) => vec![&i.operand],
+        Instruction::SExt(i) => vec![&i.operand],
+        Instruction::PtrToInt(i) => vec![&i.operand],
+        Instruction::IntToPtr(i) => vec![&i.operand],
+        Instruction::BitCast(i) => vec![&i.operand],
+        Instruction::AddrSpaceCast(i) => vec![&i.operand],
+        Instruction::Freeze(i) => vec![&i.operand],
+        Instruction::Select(i) => vec![&i.condition, &i.true_value, &i.false_value],
+        Instruction::Phi(i) => i.incoming_values.iter().map(|(v, _)| v).collect(),
+        Instruction::Call(i) => call_operands(i.function.as_ref().right(), &i.arguments),
+        _ => Vec::new(),
+    }
+}
+
+fn terminator_operands(term: &Terminator) -> Vec<&Operand> {
+    match term {
+        Terminator::Ret(t) => t.return_operand.iter().collect(),
+        Terminator::CondBr(t) => vec![&t.condition],
+        Terminator::Switch(t) => vec![&t.operand],
+        Terminator::Invoke(t) => call_operands(t.function.as_ref().right(), &t.arguments),
+        _ => Vec::new(),
+    }
+}
+
+fn call_operands<'a, T>(
+    function: Option<&'a Operand>,
+    arguments: &'a [(Operand, T)],
+) -> Vec<&'a Operand> {
+    function
+        .into_iter()
+        .chain(arguments.iter().map(|(a, _)| a))
+        .collect()
+}
+
+fn operand_globals(operand: &Operand, names: &mut BTreeSet<String>) {
+    if let Operand::ConstantOperand(c) = operand {
+        constant_globals(c, names);
+    }
+}
+
+fn constant_globals(constant: &Constant, names: &mut BTreeSet<String>) {
+    let operands: Vec<&ConstantRef> = match constant {
+        Constant::GlobalReference { name, ty: _ } => {
+            names.insert(yul_ident_name(name));
+            return;
+        }
+        Constant::Struct { values, .. } => values.iter().collect(),
+        Constant::Array { elements, .. } | Constant::Vector(elements) => elements.iter().collect(),
+        Constant::GetElementPtr(c) => iter::once(&c.address).chain(&c.indices).collect(),
+        Constant::Trunc(c) => vec![&c.operand],
+        Constant::ZExt(c) => vec![&c.operand],
+        Constant::SExt(c) => vec![&c.operand],
+        Constant::PtrToInt(c) => vec![&c.operand],
+        Constant::IntToPtr(c) => vec![&c.operand],
+        Constant::BitCast(c) => vec![&c.operand],
+        Constant::AddrSpaceCast(c) => vec![&c.operand],
+        Constant::ICmp(c) => vec![&c.operand0, &c.operand1],
+        Constant::ExtractElement(c) => vec![&c.vector, &c.index],
+        Constant::InsertElement(c) => vec![&c.vector, &c.element, &c.index],
+        Constant::ShuffleVector(c) => vec![&c.operand0, &c.operand1, &c.mask],
+        _ => match binary_constant(constant) {
+            Some((_, a, b)) => {
+                constant_globals(a, names);
+                constant_globals(b, names);
+                return;
+            }
+            None => return,
+        },
+    };
+
+    for c in operands {
+        constant_globals(c, names);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use yuler::{Block, Statement, Writer};
+
+    use super::{build_global_loader, build_statics_prologue, global_address, GlobalData};
+
+    fn write(stmts: Vec<Statement>) -> String {
+        let mut w = Writer::new(Vec::new(), "");
+        Block(stmts).write(&mut w).unwrap();
+        String::from_utf8(w.w).unwrap()
+    }
+
+    #[test]
+    fn test_build_global_loader() {
+        let data = GlobalData {
+            name: "msg".into(),
+            bytes: vec![1; 33],
+            relocations: vec![(32, global_address("other").unwrap())],
+        };
+
+        let loader: Statement = build_global_loader(&data, 384).unwrap().into();
+
+        let mut w = Writer::new(Vec::new(), "");
+        loader.write(&mut w).unwrap();
+
+        assert_eq!(
+            String::from_utf8(w.w).unwrap(),
+            "function __yn_global_msg() ->p {\n\
+             p := mload(384)\n\
+             if iszero(p) {\n\
+             p := mload(0x40)\n\
+             mstore(0x40, add(p, 64))\n\
+             mstore(384, p)\n\
+             datacopy(p, dataoffset(\"msg\"), 33)\n\
+             mstore(add(p, 32), __yn_global_other())\n\
+             }\n\
+             }\n"
+        );
+    }
+
+    #[test]
+    fn test_build_statics_prologue() {
+        let mut bytes = vec![0; 64];
+        bytes[31] = 5;
+
+        let mut data = GlobalData {
+            name: "__yn_statics".into(),
+            bytes,
+            relocations: vec![(32, global_address("msg").unwrap())],
+        };
+
+        let prologue = build_statics_prologue(128, 224, &mut data).unwrap();
+
+        assert_eq!(data.bytes.len(), 32);
+        assert_eq!(
+            write(prologue),
+            "{\n\
+             mstore(0x40, 224)\n\
+             datacopy(128, dataoffset(\"__yn_statics\"), 32)\n\
+             mstore(160, __yn_global_msg())\n\
+             }"
+        );
+    }
+}
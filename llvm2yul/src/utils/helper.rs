@@ -0,0 +1,350 @@
+use anyhow::{anyhow, Result};
+use yuler::{
+    Assignment, CaseBlock, ForLoop, FunctionDefinition, Ident, If, InlineBlock, Literal, Statement,
+    Switch, Value, VariableDeclare,
+};
+
+use super::{bit_mask, builtin_call, masked_store, splat_byte};
+
+pub const MEMCPY_HELPER: &str = "__yn_memcpy";
+pub const MEMMOVE_HELPER: &str = "__yn_memmove";
+pub const MEMSET_HELPER: &str = "__yn_memset";
+pub const CTPOP_HELPER: &str = "__yn_ctpop";
+pub const CTLZ_HELPER: &str = "__yn_ctlz";
+pub const CTTZ_HELPER: &str = "__yn_cttz";
+pub const BSWAP_HELPER: &str = "__yn_bswap";
+pub const BITREVERSE_HELPER: &str = "__yn_bitreverse";
+pub const FSHL_HELPER: &str = "__yn_fshl";
+pub const FSHR_HELPER: &str = "__yn_fshr";
+
+/// Build helper function which is generated by compiler.
+///
+/// Helpers are added into object only when they are used.
+pub fn build_helper(name: &str) -> Result<FunctionDefinition> {
+    match name {
+        MEMCPY_HELPER => build_function(name, &["dst", "src", "len"], &[], build_copy_forward()?),
+        MEMMOVE_HELPER => build_function(name, &["dst", "src", "len"], &[], build_memmove()?),
+        MEMSET_HELPER => build_function(name, &["dst", "val", "len"], &[], build_memset()?),
+        CTPOP_HELPER => build_function(name, &["x", "bits"], &["r"], build_ctpop()?),
+        CTLZ_HELPER => build_function(name, &["x", "bits"], &["r"], build_ctlz()?),
+        CTTZ_HELPER => build_function(name, &["x", "bits"], &["r"], build_cttz()?),
+        BSWAP_HELPER => build_function(name, &["x", "bits"], &["r"], build_reverse(8)?),
+        BITREVERSE_HELPER => build_function(name, &["x", "bits"], &["r"], build_reverse(1)?),
+        FSHL_HELPER => build_function(name, &["a", "b", "c", "bits"], &["r"], build_funnel(true)?),
+        FSHR_HELPER => build_function(name, &["a", "b", "c", "bits"], &["r"], build_funnel(false)?),
+        _ => Err(anyhow!("Unknown helper function: {name}")),
+    }
+}
+
+fn build_function(
+    name: &str,
+    args: &[&str],
+    rets: &[&str],
+    body: Vec<Statement>,
+) -> Result<FunctionDefinition> {
+    let mut func = FunctionDefinition::new(Ident::new(name)?);
+
+    for a in args {
+        func.args.push(Ident::new(*a)?);
+    }
+
+    for r in rets {
+        func.rets.push(Ident::new(*r)?);
+    }
+
+    func.block = body.into();
+
+    Ok(func)
+}
+
+/// Copy words from start, then copy remaining bytes.
+///
+/// ```text
+/// let i := 0
+/// for {} iszero(gt(add(i, 32), len)) { i := add(i, 32) } {
+///     mstore(add(dst, i), mload(add(src, i)))
+/// }
+/// if lt(i, len) { .. }
+/// ```
+fn build_copy_forward() -> Result<Vec<Statement>> {
+    let word = call("mload", vec![call("add", vec![var("src")?, var("i")?])?])?;
+
+    Ok(vec![
+        declare("i", num(0)?)?,
+        build_word_loop(word.clone())?,
+        build_tail(word)?,
+    ])
+}
+
+/// Copy backward when `dst` is after `src`, so overlapped bytes are read before written.
+fn build_memmove() -> Result<Vec<Statement>> {
+    let offset = call("sub", vec![var("i")?, num(32)?])?;
+
+    let backward = vec![
+        declare("i", var("len")?)?,
+        ForLoop {
+            init: Default::default(),
+            cond: call("gt", vec![var("i")?, num(31)?])?,
+            incr: InlineBlock(vec![assign("i", call("sub", vec![var("i")?, num(32)?])?)?]),
+            block: vec![builtin_call(
+                "mstore",
+                vec![
+                    call("add", vec![var("dst")?, offset.clone()])?,
+                    call("mload", vec![call("add", vec![var("src")?, offset])?])?,
+                ],
+            )?
+            .into()]
+            .into(),
+        }
+        .into(),
+        If {
+            cond: var("i")?,
+            block: vec![masked_store(
+                var("dst")?,
+                call("mload", vec![var("src")?])?,
+                low_mask(var("i")?)?,
+            )?]
+            .into(),
+        }
+        .into(),
+    ];
+
+    Ok(vec![Switch {
+        cond: call("gt", vec![var("dst")?, var("src")?])?,
+        cases: vec![CaseBlock {
+            cond: Literal::int_number(0)?,
+            block: build_copy_forward()?.into(),
+        }],
+        default: Some(backward.into()),
+    }
+    .into()])
+}
+
+fn build_memset() -> Result<Vec<Statement>> {
+    Ok(vec![
+        declare("word", splat_byte(var("val")?)?)?,
+        declare("i", num(0)?)?,
+        build_word_loop(var("word")?)?,
+        build_tail(var("word")?)?,
+    ])
+}
+
+/// Count set bits, each iteration clears the lowest set bit.
+fn build_ctpop() -> Result<Vec<Statement>> {
+    Ok(vec![
+        assign("x", call("and", vec![var("x")?, width_mask()?])?)?,
+        ForLoop {
+            init: Default::default(),
+            cond: var("x")?,
+            incr: InlineBlock(vec![assign(
+                "x",
+                call(
+                    "and",
+                    vec![var("x")?, call("sub", vec![var("x")?, num(1)?])?],
+                )?,
+            )?]),
+            block: vec![assign("r", call("add", vec![var("r")?, num(1)?])?)?].into(),
+        }
+        .into(),
+    ])
+}
+
+/// Count leading zeros of 256 bits by binary search, then subtract bits out of width.
+fn build_ctlz() -> Result<Vec<Statement>> {
+    let mut res = vec![
+        assign("x", call("and", vec![var("x")?, width_mask()?])?)?,
+        build_zero_return()?,
+    ];
+
+    for k in [128, 64, 32, 16, 8, 4, 2, 1] {
+        let high = call("shr", vec![num(256 - k)?, var("x")?])?;
+
+        res.push(
+            If {
+                cond: call("iszero", vec![high])?,
+                block: vec![
+                    assign("r", call("add", vec![var("r")?, num(k)?])?)?,
+                    assign("x", call("shl", vec![num(k)?, var("x")?])?)?,
+                ]
+                .into(),
+            }
+            .into(),
+        );
+    }
+
+    let out = call("sub", vec![num(256)?, var("bits")?])?;
+    res.push(assign("r", call("sub", vec![var("r")?, out])?)?);
+
+    Ok(res)
+}
+
+/// Count trailing zeros by binary search.
+fn build_cttz() -> Result<Vec<Statement>> {
+    let mut res = vec![
+        assign("x", call("and", vec![var("x")?, width_mask()?])?)?,
+        build_zero_return()?,
+    ];
+
+    for k in [128, 64, 32, 16, 8, 4, 2, 1] {
+        let low = call("and", vec![var("x")?, bit_mask(k as u32)?.into()])?;
+
+        res.push(
+            If {
+                cond: call("iszero", vec![low])?,
+                block: vec![
+                    assign("r", call("add", vec![var("r")?, num(k)?])?)?,
+                    assign("x", call("shr", vec![num(k)?, var("x")?])?)?,
+                ]
+                .into(),
+            }
+            .into(),
+        );
+    }
+
+    Ok(res)
+}
+
+/// Zero has `bits` leading or trailing zeros.
+fn build_zero_return() -> Result<Statement> {
+    Ok(If {
+        cond: call("iszero", vec![var("x")?])?,
+        block: vec![assign("r", var("bits")?)?, Statement::Leave].into(),
+    }
+    .into())
+}
+
+/// Reverse order of `unit` bits chunks in `bits` bits, byte for `bswap` and bit for `bitreverse`.
+fn build_reverse(unit: u64) -> Result<Vec<Statement>> {
+    let chunk = call("and", vec![var("x")?, bit_mask(unit as u32)?.into()])?;
+
+    Ok(vec![ForLoop {
+        init: InlineBlock(vec![declare("i", num(0)?)?]),
+        cond: call("lt", vec![var("i")?, var("bits")?])?,
+        incr: InlineBlock(vec![assign(
+            "i",
+            call("add", vec![var("i")?, num(unit)?])?,
+        )?]),
+        block: vec![
+            assign(
+                "r",
+                call("or", vec![call("shl", vec![num(unit)?, var("r")?])?, chunk])?,
+            )?,
+            assign("x", call("shr", vec![num(unit)?, var("x")?])?)?,
+        ]
+        .into(),
+    }
+    .into()])
+}
+
+/// Funnel shift of concatenated `a` and `b`, `a` is high part.
+///
+/// `fshl` takes high `bits` bits after shifting left, `fshr` takes low `bits` bits after
+/// shifting right. Shift amount is modulo `bits`.
+fn build_funnel(left: bool) -> Result<Vec<Statement>> {
+    let (first, second, forward, backward) = if left {
+        ("a", "b", "shl", "shr")
+    } else {
+        ("b", "a", "shr", "shl")
+    };
+
+    let shifted = call(forward, vec![var("s")?, var("r")?])?;
+    let rest = call(
+        backward,
+        vec![
+            call("sub", vec![var("bits")?, var("s")?])?,
+            call("and", vec![var(second)?, var("m")?])?,
+        ],
+    )?;
+
+    Ok(vec![
+        declare("m", width_mask()?)?,
+        declare("s", call("mod", vec![var("c")?, var("bits")?])?)?,
+        assign("r", call("and", vec![var(first)?, var("m")?])?)?,
+        If {
+            cond: var("s")?,
+            block: vec![assign(
+                "r",
+                call("and", vec![call("or", vec![shifted, rest])?, var("m")?])?,
+            )?]
+            .into(),
+        }
+        .into(),
+    ])
+}
+
+/// Mask of `bits` bits, `shl(256, 1)` is 0 so it's also right for 256 bits.
+fn width_mask() -> Result<Value> {
+    call(
+        "sub",
+        vec![call("shl", vec![var("bits")?, num(1)?])?, num(1)?],
+    )
+}
+
+/// Store `word` at `add(dst, i)` while a whole word remains.
+fn build_word_loop(word: Value) -> Result<Statement> {
+    let end = call("add", vec![var("i")?, num(32)?])?;
+
+    Ok(ForLoop {
+        init: Default::default(),
+        cond: call("iszero", vec![call("gt", vec![end, var("len")?])?])?,
+        incr: InlineBlock(vec![assign("i", call("add", vec![var("i")?, num(32)?])?)?]),
+        block: vec![builtin_call(
+            "mstore",
+            vec![call("add", vec![var("dst")?, var("i")?])?, word],
+        )?
+        .into()]
+        .into(),
+    }
+    .into())
+}
+
+/// Store first `sub(len, i)` bytes of `word` at `add(dst, i)`.
+fn build_tail(word: Value) -> Result<Statement> {
+    let remain = call("sub", vec![var("len")?, var("i")?])?;
+
+    Ok(If {
+        cond: call("lt", vec![var("i")?, var("len")?])?,
+        block: vec![masked_store(
+            call("add", vec![var("dst")?, var("i")?])?,
+            word,
+            low_mask(remain)?,
+        )?]
+        .into(),
+    }
+    .into())
+}
+
+/// Mask of bits after first `len` bytes.
+fn low_mask(len: Value) -> Result<Value> {
+    let bits = call("shl", vec![num(3)?, len])?;
+
+    call("shr", vec![bits, call("not", vec![num(0)?])?])
+}
+
+fn call(name: &str, args: Vec<Value>) -> Result<Value> {
+    Ok(builtin_call(name, args)?.into())
+}
+
+fn var(name: &str) -> Result<Value> {
+    Ok(Ident::new(name)?.into())
+}
+
+fn num(n: u64) -> Result<Value> {
+    Ok(Literal::int_number(n)?.into())
+}
+
+fn declare(name: &str, value: Value) -> Result<Statement> {
+    Ok(VariableDeclare {
+        names: vec![Ident::new(name)?],
+        value,
+    }
+    .into())
+}
+
+fn assign(name: &str, value: Value) -> Result<Statement> {
+    Ok(Assignment {
+        names: vec![Ident::new(name)?],
+        value,
+    }
+    .into())
+}
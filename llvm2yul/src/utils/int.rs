@@ -0,0 +1,251 @@
+use anyhow::{anyhow, Result};
+use primitive_types::U256;
+use yuler::{FunctionCall, Literal, Value};
+
+use super::builtin_call;
+
+/// Literal with low `bits` bits set.
+pub fn bit_mask(bits: u32) -> Result<Literal> {
+    let mut s = String::from("0x");
+
+    let head = bits % 4;
+    if head != 0 {
+        s.push_str(&format!("{:x}", (1u8 << head) - 1));
+    }
+
+    for _ in 0..bits / 4 {
+        s.push('f');
+    }
+
+    Literal::hex_number(s)
+}
+
+/// Wrap value around to `bits` bits. Literal is folded.
+pub fn mask_bits(value: Value, bits: u32) -> Result<Value> {
+    if bits >= 256 {
+        return Ok(value);
+    }
+
+    if let Value::Literal(literal) = &value {
+        if let Some(v) = literal.as_u256() {
+            return Ok(Literal::u256_number(v & ((U256::one() << bits) - 1))?.into());
+        }
+    }
+
+    Ok(builtin_call("and", vec![value, bit_mask(bits)?.into()])?.into())
+}
+
+/// Sign extend value of `bits` bits to 256 bits. Literal is folded into a sign extended word.
+pub fn sign_extend(value: Value, bits: u32) -> Result<Value> {
+    if bits >= 256 {
+        return Ok(value);
+    }
+
+    if let Value::Literal(literal) = &value {
+        if let Some(v) = literal.as_u256() {
+            return Ok(Literal::u256_number(sign_extend_word(v, bits))?.into());
+        }
+    }
+
+    if bits.is_multiple_of(8) {
+        let b = Literal::int_number(u64::from(bits / 8 - 1))?;
+
+        Ok(builtin_call("signextend", vec![b.into(), value])?.into())
+    } else {
+        let shift = Literal::int_number(u64::from(256 - bits))?;
+
+        let value = builtin_call("shl", vec![shift.clone().into(), value])?;
+
+        Ok(builtin_call("sar", vec![shift.into(), value.into()])?.into())
+    }
+}
+
+/// Sign extend constant of `bits` bits to 256 bits.
+pub fn sign_extend_word(value: U256, bits: u32) -> U256 {
+    if bits == 0 || bits >= 256 {
+        return value;
+    }
+
+    let value = value & ((U256::one() << bits) - 1);
+
+    if value.bit(bits as usize - 1) {
+        value | (U256::MAX << bits)
+    } else {
+        value
+    }
+}
+
+/// Evaluate builtin calls whose arguments are all literals, other values are kept.
+pub fn fold_value(value: Value) -> Result<Value> {
+    let FunctionCall { name, args } = match value {
+        Value::FunctionCall(call) => call,
+        v => return Ok(v),
+    };
+
+    let args = args
+        .into_iter()
+        .map(fold_value)
+        .collect::<Result<Vec<Value>>>()?;
+
+    let words: Option<Vec<U256>> = args
+        .iter()
+        .map(|a| a.as_literal().and_then(|l| l.as_u256()))
+        .collect();
+
+    if let Some(v) = words.and_then(|w| eval_builtin(&name.0, &w)) {
+        return Ok(Literal::u256_number(v)?.into());
+    }
+
+    Ok(FunctionCall { name, args }.into())
+}
+
+fn eval_builtin(name: &str, args: &[U256]) -> Option<U256> {
+    let bool_word = |b: bool| if b { U256::one() } else { U256::zero() };
+    let signed = |v: U256| v ^ (U256::one() << 255);
+    let shift = |v: U256| {
+        if v >= U256::from(256) {
+            None
+        } else {
+            Some(v.as_usize())
+        }
+    };
+
+    let v = match (name, args) {
+        ("add", &[a, b]) => a.overflowing_add(b).0,
+        ("sub", &[a, b]) => a.overflowing_sub(b).0,
+        ("mul", &[a, b]) => a.overflowing_mul(b).0,
+        ("and", &[a, b]) => a & b,
+        ("or", &[a, b]) => a | b,
+        ("xor", &[a, b]) => a ^ b,
+        ("not", &[a]) => !a,
+        ("iszero", &[a]) => bool_word(a.is_zero()),
+        ("eq", &[a, b]) => bool_word(a == b),
+        ("lt", &[a, b]) => bool_word(a < b),
+        ("gt", &[a, b]) => bool_word(a > b),
+        ("slt", &[a, b]) => bool_word(signed(a) < signed(b)),
+        ("sgt", &[a, b]) => bool_word(signed(a) > signed(b)),
+        ("shl", &[s, a]) => shift(s).map_or(U256::zero(), |s| a << s),
+        ("shr", &[s, a]) => shift(s).map_or(U256::zero(), |s| a >> s),
+        ("sar", &[s, a]) => {
+            let negative = a.bit(255);
+
+            match shift(s) {
+                Some(s) if negative => !(!a >> s),
+                Some(s) => a >> s,
+                None if negative => U256::MAX,
+                None => U256::zero(),
+            }
+        }
+        ("byte", &[i, a]) if i < U256::from(32) => {
+            (a >> (8 * (31 - i.as_usize()))) & U256::from(0xff)
+        }
+        ("byte", &[_, _]) => U256::zero(),
+        ("signextend", &[b, a]) if b < U256::from(31) => sign_extend_word(a, 8 * (b.as_u32() + 1)),
+        ("signextend", &[_, a]) => a,
+        _ => return None,
+    };
+
+    Some(v)
+}
+
+/// Parse number in string of `__yul__ext_literal_str`, which is decimal or hex with `0x` prefix.
+pub fn parse_literal_str(s: &str) -> Result<U256> {
+    let err = || anyhow!("Malformed number of __yul__ext_literal_str: {s:?}");
+
+    match s.strip_prefix("0x").or(s.strip_prefix("0X")) {
+        Some(hex) => {
+            if hex.is_empty() || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(err());
+            }
+
+            U256::from_str_radix(hex, 16).map_err(|_| err())
+        }
+        None => {
+            if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(err());
+            }
+
+            U256::from_dec_str(s).map_err(|_| err())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use primitive_types::U256;
+    use yuler::Writer;
+
+    use yuler::{Ident, Literal, Value};
+
+    use super::{bit_mask, fold_value, parse_literal_str, sign_extend_word};
+    use crate::utils::builtin_call;
+
+    fn write(bits: u32) -> String {
+        let mut w = Writer::new(Vec::new(), "");
+        bit_mask(bits).unwrap().write(&mut w.w).unwrap();
+        String::from_utf8(w.w).unwrap()
+    }
+
+    #[test]
+    fn test_bit_mask() {
+        assert_eq!(write(1), "0x1");
+        assert_eq!(write(8), "0xff");
+        assert_eq!(write(10), "0x3ff");
+        assert_eq!(write(64), "0xffffffffffffffff");
+    }
+
+    #[test]
+    fn test_sign_extend_word() {
+        assert_eq!(sign_extend_word(U256::from(u64::MAX), 64), U256::MAX);
+        assert_eq!(sign_extend_word(U256::from(0x7f), 8), U256::from(0x7f));
+        assert_eq!(sign_extend_word(U256::from(0x80), 8), U256::MAX << 7);
+        assert_eq!(sign_extend_word(U256::from(0x1ff), 8), U256::MAX);
+    }
+
+    #[test]
+    fn test_parse_literal_str() {
+        assert_eq!(parse_literal_str("42").unwrap(), U256::from(42));
+        assert_eq!(parse_literal_str("0xff").unwrap(), U256::from(255));
+        assert_eq!(
+            parse_literal_str(&format!("0x{}", "f".repeat(64))).unwrap(),
+            U256::MAX
+        );
+
+        assert!(parse_literal_str("").is_err());
+        assert!(parse_literal_str("0x").is_err());
+        assert!(parse_literal_str("-1").is_err());
+        assert!(parse_literal_str("12a").is_err());
+        assert!(parse_literal_str(&format!("0x1{}", "0".repeat(64))).is_err());
+    }
+
+    #[test]
+    fn test_fold_value() {
+        let num = |n: u64| -> Value { Literal::int_number(n).unwrap().into() };
+        let call =
+            |name: &str, args: Vec<Value>| -> Value { builtin_call(name, args).unwrap().into() };
+        let word = |v: Value| {
+            fold_value(v)
+                .unwrap()
+                .as_literal()
+                .and_then(|l| l.as_u256())
+        };
+
+        let v = call("add", vec![call("shl", vec![num(8), num(1)]), num(2)]);
+        assert_eq!(word(v), Some(U256::from(258)));
+
+        let v = call("sar", vec![num(4), call("sub", vec![num(0), num(32)])]);
+        assert_eq!(word(v), Some(U256::MAX - 1));
+
+        let v = call("slt", vec![call("not", vec![num(0)]), num(0)]);
+        assert_eq!(word(v), Some(U256::one()));
+
+        let x: Value = Ident::new("x").unwrap().into();
+        let v = call("add", vec![x, call("mul", vec![num(2), num(3)])]);
+        assert_eq!(word(v.clone()), None);
+
+        match fold_value(v).unwrap() {
+            Value::FunctionCall(c) => assert_eq!(word(c.args[1].clone()), Some(U256::from(6))),
+            _ => panic!("add of variable is kept"),
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use anyhow::{anyhow, Result};
+use llvm_ir::Type;
+use yuler::{Literal, Statement, Value};
+
+use super::{bit_mask, builtin_call};
+use crate::{error, Config, DataLocation};
+
+/// Address of value at `offset`, constant address is folded.
+pub fn offset_address(base: &Value, offset: u64) -> Result<Value> {
+    if offset == 0 {
+        return Ok(base.clone());
+    }
+
+    if let Some(base) = base.as_literal().and_then(|l| l.as_number()) {
+        let address = base
+            .checked_add(offset)
+            .ok_or(anyhow!("{} address overflow: {base}", error::WRONG_ARG))?;
+
+        return Ok(Literal::int_number(address)?.into());
+    }
+
+    Ok(builtin_call(
+        "add",
+        vec![base.clone(), Literal::int_number(offset)?.into()],
+    )?
+    .into())
+}
+
+/// Data location which pointer of `ty` points to.
+pub fn pointer_location(ty: &Type, config: &Config) -> DataLocation {
+    match ty {
+        Type::PointerType { addr_space } => config.data_location(*addr_space),
+        _ => DataLocation::Memory,
+    }
+}
+
+/// Transient storage is error before cancun.
+fn check_location(location: DataLocation, config: &Config) -> Result<()> {
+    if location == DataLocation::Transient && !config.evm_version.has_transient() {
+        return Err(anyhow!(
+            "{} transient storage on {:?}",
+            error::UNSUPPERTED_OPERAND,
+            config.evm_version
+        ));
+    }
+
+    Ok(())
+}
+
+/// Load value of `size` bytes at `address` as big endian number, returns statements run before
+/// and the value.
+///
+/// Slot of storage and transient storage keeps a whole value even if it's a byte. Code is
+/// copied into scratch memory at `0` first.
+pub fn load_from(
+    config: &Config,
+    location: DataLocation,
+    address: Value,
+    size: u64,
+) -> Result<(Vec<Statement>, Value)> {
+    check_location(location, config)?;
+
+    let zero: Value = Literal::int_number(0)?.into();
+
+    let (pre, word) = match location {
+        DataLocation::Memory => (vec![], builtin_call("mload", vec![address])?),
+        DataLocation::Storage => return Ok((vec![], builtin_call("sload", vec![address])?.into())),
+        DataLocation::Transient => {
+            return Ok((vec![], builtin_call("tload", vec![address])?.into()))
+        }
+        DataLocation::Calldata => (vec![], builtin_call("calldataload", vec![address])?),
+        DataLocation::Code => {
+            let copy = builtin_call(
+                "codecopy",
+                vec![zero.clone(), address, Literal::int_number(32)?.into()],
+            )?;
+
+            (vec![copy.into()], builtin_call("mload", vec![zero])?)
+        }
+    };
+
+    let value = if size < 32 {
+        let shift = Literal::int_number(256 - 8 * size)?;
+
+        builtin_call("shr", vec![shift.into(), word.into()])?
+    } else {
+        word
+    };
+
+    Ok((pre, value.into()))
+}
+
+/// Store `value` of `size` bytes at `address`, read only location is error.
+///
+/// Value shorter than a word is kept as big endian number, other bytes of memory are kept.
+pub fn store_to(
+    config: &Config,
+    location: DataLocation,
+    address: Value,
+    value: Value,
+    size: u64,
+) -> Result<Statement> {
+    check_location(location, config)?;
+
+    let name = match location {
+        DataLocation::Memory if size == 1 => "mstore8",
+        DataLocation::Memory if size < 32 => {
+            let shift = Literal::int_number(256 - 8 * size)?;
+            let word = builtin_call("shl", vec![shift.into(), value])?;
+
+            return store_bytes(address, word.into(), size);
+        }
+        DataLocation::Memory => "mstore",
+        DataLocation::Storage => "sstore",
+        DataLocation::Transient => "tstore",
+        DataLocation::Calldata | DataLocation::Code => {
+            return Err(anyhow!(
+                "{} store to read only {location:?}",
+                error::UNSUPPERTED_OPERAND
+            ))
+        }
+    };
+
+    Ok(builtin_call(name, vec![address, value])?.into())
+}
+
+/// Store the first `len` bytes of `word` at `address`, other bytes of memory are kept.
+pub fn store_bytes(address: Value, word: Value, len: u64) -> Result<Statement> {
+    if len >= 32 {
+        return Ok(builtin_call("mstore", vec![address, word])?.into());
+    }
+
+    masked_store(address, word, bit_mask(256 - 8 * len as u32)?.into())
+}
+
+/// Store `word` at `address`, but keep bits of memory which are set in `mask`.
+pub fn masked_store(address: Value, word: Value, mask: Value) -> Result<Statement> {
+    let keep = builtin_call(
+        "and",
+        vec![
+            builtin_call("mload", vec![address.clone()])?.into(),
+            mask.clone(),
+        ],
+    )?;
+
+    let word = builtin_call("and", vec![word, builtin_call("not", vec![mask])?.into()])?;
+
+    let value = builtin_call("or", vec![word.into(), keep.into()])?;
+
+    Ok(builtin_call("mstore", vec![address, value.into()])?.into())
+}
+
+/// Word which every byte is the low byte of `value`.
+pub fn splat_byte(value: Value) -> Result<Value> {
+    if let Some(v) = value.as_literal().and_then(|l| l.as_number()) {
+        let byte = v & 0xff;
+
+        if byte == 0 {
+            return Ok(Literal::int_number(0)?.into());
+        }
+
+        return Ok(Literal::hex_number(format!("0x{}", format!("{byte:02x}").repeat(32)))?.into());
+    }
+
+    let byte = builtin_call("and", vec![value, Literal::int_number(0xff)?.into()])?;
+    let multiplier = Literal::hex_number(format!("0x{}", "01".repeat(32)))?;
+
+    Ok(builtin_call("mul", vec![byte.into(), multiplier.into()])?.into())
+}
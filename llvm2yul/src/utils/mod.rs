@@ -9,3 +9,27 @@ pub use types::*;
 
 mod builtin;
 pub use builtin::*;
+
+mod control_block;
+pub use control_block::*;
+
+mod int;
+pub use int::*;
+
+mod memory;
+pub use memory::*;
+
+mod helper;
+pub use helper::*;
+
+mod dispatch;
+pub use dispatch::*;
+
+mod wide_int;
+pub use wide_int::*;
+
+mod const_gep;
+pub use const_gep::*;
+
+mod global;
+pub use global::*;
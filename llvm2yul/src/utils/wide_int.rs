@@ -0,0 +1,312 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ffi::{c_char, CStr, CString},
+    path::Path,
+    ptr,
+};
+
+use anyhow::{anyhow, Result};
+use llvm_sys::{
+    core::{
+        LLVMConstArray2, LLVMConstInt, LLVMConstNamedStruct, LLVMConstVector, LLVMContextCreate,
+        LLVMContextDispose, LLVMCreateMemoryBufferWithContentsOfFile,
+        LLVMCreateMemoryBufferWithMemoryRangeCopy, LLVMDisposeMessage, LLVMDisposeModule,
+        LLVMGetElementType, LLVMGetFirstBasicBlock, LLVMGetFirstFunction, LLVMGetFirstGlobal,
+        LLVMGetFirstInstruction, LLVMGetInitializer, LLVMGetIntTypeWidth, LLVMGetNextBasicBlock,
+        LLVMGetNextFunction, LLVMGetNextGlobal, LLVMGetNextInstruction, LLVMGetNumOperands,
+        LLVMGetOperand, LLVMIsAConstantArray, LLVMIsAConstantExpr, LLVMIsAConstantInt,
+        LLVMIsAConstantStruct, LLVMIsAConstantVector, LLVMPrintModuleToString,
+        LLVMPrintValueToString, LLVMSetInitializer, LLVMSetOperand, LLVMTypeOf,
+    },
+    ir_reader::LLVMParseIRInContext,
+    prelude::{LLVMMemoryBufferRef, LLVMModuleRef, LLVMValueRef},
+};
+use primitive_types::U256;
+
+use super::mark_const_geps;
+use crate::error;
+
+/// First tag of wide integer constant.
+const WIDE_TAG_BASE: u64 = 0x7969_6465_0000_0000;
+
+/// Read textual IR or bitcode, then tag wide integer constants by `tag_wide_constants`.
+///
+/// Constant `getelementptr` is marked by `mark_const_geps` too.
+pub fn read_wide_ir(path: &Path) -> Result<(String, BTreeMap<u64, U256>)> {
+    let path = CString::new(path.to_string_lossy().as_bytes())?;
+
+    let mut buffer = ptr::null_mut();
+    let mut message = ptr::null_mut();
+
+    unsafe {
+        if LLVMCreateMemoryBufferWithContentsOfFile(path.as_ptr(), &mut buffer, &mut message) != 0 {
+            return Err(anyhow!("Failed to open module: {}", take_message(message)));
+        }
+
+        tag_buffer(buffer)
+    }
+}
+
+/// Replace integer constants wider than 64 bits which don't fit in `u64` with tags, returns
+/// printed IR and value of each tag.
+///
+/// `llvm_ir` truncates integer constant to `u64`, so wide constants are tagged before it parses
+/// module, and restored by `ConstantFlatter`. Tags never equal to other wide constant in module.
+pub fn tag_wide_constants(ir: &str) -> Result<(String, BTreeMap<u64, U256>)> {
+    let name = CString::new("ir")?;
+
+    unsafe {
+        let buffer = LLVMCreateMemoryBufferWithMemoryRangeCopy(
+            ir.as_ptr() as *const _,
+            ir.len(),
+            name.as_ptr(),
+        );
+
+        tag_buffer(buffer)
+    }
+}
+
+unsafe fn tag_buffer(buffer: LLVMMemoryBufferRef) -> Result<(String, BTreeMap<u64, U256>)> {
+    let context = LLVMContextCreate();
+
+    let mut module = ptr::null_mut();
+    let mut message = ptr::null_mut();
+
+    // Buffer is owned by parser.
+    if LLVMParseIRInContext(context, buffer, &mut module, &mut message) != 0 {
+        LLVMContextDispose(context);
+
+        return Err(anyhow!("Failed to open module: {}", take_message(message)));
+    }
+
+    let mut tagger = Tagger::default();
+
+    let res = tagger.visit_module(module).and_then(|_| {
+        tagger.replace = true;
+        tagger.visit_module(module)
+    });
+
+    if res.is_ok() {
+        mark_const_geps(module);
+    }
+
+    let ir = take_message(LLVMPrintModuleToString(module));
+
+    LLVMDisposeModule(module);
+    LLVMContextDispose(context);
+
+    res.map(|_| (ir, tagger.values))
+}
+
+unsafe fn take_message(message: *mut c_char) -> String {
+    if message.is_null() {
+        return String::new();
+    }
+
+    let s = CStr::from_ptr(message).to_string_lossy().into_owned();
+    LLVMDisposeMessage(message);
+
+    s
+}
+
+/// Collect wide constants which fit in `u64` at first pass, then replace others at second pass.
+#[derive(Default)]
+struct Tagger {
+    replace: bool,
+    used: BTreeSet<u64>,
+    tags: BTreeMap<U256, u64>,
+    values: BTreeMap<u64, U256>,
+}
+
+impl Tagger {
+    unsafe fn visit_module(&mut self, module: LLVMModuleRef) -> Result<()> {
+        let mut global = LLVMGetFirstGlobal(module);
+        while !global.is_null() {
+            let init = LLVMGetInitializer(global);
+
+            if !init.is_null() {
+                if let Some(c) = self.visit_constant(init)? {
+                    LLVMSetInitializer(global, c);
+                }
+            }
+
+            global = LLVMGetNextGlobal(global);
+        }
+
+        let mut func = LLVMGetFirstFunction(module);
+        while !func.is_null() {
+            let mut bb = LLVMGetFirstBasicBlock(func);
+            while !bb.is_null() {
+                let mut inst = LLVMGetFirstInstruction(bb);
+                while !inst.is_null() {
+                    for i in 0..LLVMGetNumOperands(inst) {
+                        let op = LLVMGetOperand(inst, i as u32);
+
+                        if let Some(c) = self.visit_constant(op)? {
+                            LLVMSetOperand(inst, i as u32, c);
+                        }
+                    }
+
+                    inst = LLVMGetNextInstruction(inst);
+                }
+
+                bb = LLVMGetNextBasicBlock(bb);
+            }
+
+            func = LLVMGetNextFunction(func);
+        }
+
+        Ok(())
+    }
+
+    /// Returns new constant if constant is changed.
+    ///
+    /// Constant expression can't be rebuilt with new operands, so wide constant in it is error.
+    unsafe fn visit_constant(&mut self, c: LLVMValueRef) -> Result<Option<LLVMValueRef>> {
+        if c.is_null() {
+            return Ok(None);
+        }
+
+        if !LLVMIsAConstantInt(c).is_null() {
+            return self.visit_int(c);
+        }
+
+        let is_struct = !LLVMIsAConstantStruct(c).is_null();
+        let is_vector = !LLVMIsAConstantVector(c).is_null();
+        let is_expr = !LLVMIsAConstantExpr(c).is_null();
+        if !is_struct && !is_vector && !is_expr && LLVMIsAConstantArray(c).is_null() {
+            return Ok(None);
+        }
+
+        let mut changed = false;
+        let mut elems = Vec::new();
+
+        for i in 0..LLVMGetNumOperands(c) {
+            let elem = LLVMGetOperand(c, i as u32);
+
+            match self.visit_constant(elem)? {
+                Some(e) => {
+                    changed = true;
+                    elems.push(e);
+                }
+                None => elems.push(elem),
+            }
+        }
+
+        if !changed {
+            return Ok(None);
+        }
+
+        if is_expr {
+            return Err(anyhow!(
+                "{} integer constant wider than 64 bits in {}",
+                error::UNSUPPERTED_OPERAND,
+                take_message(LLVMPrintValueToString(c))
+            ));
+        }
+
+        let ty = LLVMTypeOf(c);
+        let c = if is_struct {
+            LLVMConstNamedStruct(ty, elems.as_mut_ptr(), elems.len() as u32)
+        } else if is_vector {
+            LLVMConstVector(elems.as_mut_ptr(), elems.len() as u32)
+        } else {
+            LLVMConstArray2(
+                LLVMGetElementType(ty),
+                elems.as_mut_ptr(),
+                elems.len() as u64,
+            )
+        };
+
+        Ok(Some(c))
+    }
+
+    unsafe fn visit_int(&mut self, c: LLVMValueRef) -> Result<Option<LLVMValueRef>> {
+        let ty = LLVMTypeOf(c);
+        let bits = LLVMGetIntTypeWidth(ty);
+
+        if bits <= 64 {
+            return Ok(None);
+        }
+
+        // Printed as `i256 -1`.
+        let s = take_message(LLVMPrintValueToString(c));
+        let digits = s.rsplit(' ').next().unwrap_or_default();
+        let value = parse_int(digits, bits)?;
+
+        if value <= U256::from(u64::MAX) {
+            self.used.insert(value.as_u64());
+
+            return Ok(None);
+        }
+
+        if !self.replace {
+            return Ok(None);
+        }
+
+        let tag = match self.tags.get(&value) {
+            Some(tag) => *tag,
+            None => {
+                let mut tag = WIDE_TAG_BASE + self.tags.len() as u64;
+                while self.used.contains(&tag) || self.values.contains_key(&tag) {
+                    tag += 1;
+                }
+
+                self.tags.insert(value, tag);
+                self.values.insert(tag, value);
+
+                tag
+            }
+        };
+
+        Ok(Some(LLVMConstInt(ty, tag, 0)))
+    }
+}
+
+/// Parse decimal integer of `bits` bits, negative value is in two's complement.
+fn parse_int(s: &str, bits: u32) -> Result<U256> {
+    if bits > 256 {
+        return Err(anyhow!(
+            "{} integer constant of {bits} bits",
+            error::UNSUPPERTED_OPERAND
+        ));
+    }
+
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(d) => (true, d),
+        None => (false, s),
+    };
+
+    let value = U256::from_dec_str(digits)
+        .map_err(|e| anyhow!("{} integer {s}: {e:?}", error::WRONG_ARG))?;
+
+    let value = if negative {
+        (!value).overflowing_add(U256::one()).0
+    } else {
+        value
+    };
+
+    if bits == 256 {
+        Ok(value)
+    } else {
+        Ok(value & ((U256::one() << bits) - 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use primitive_types::U256;
+
+    use super::parse_int;
+
+    #[test]
+    fn test_parse_int() {
+        assert_eq!(parse_int("-1", 128).unwrap(), (U256::one() << 128) - 1);
+        assert_eq!(parse_int("-1", 256).unwrap(), U256::MAX);
+        assert_eq!(
+            parse_int("18446744073709551616", 256).unwrap(),
+            U256::one() << 64
+        );
+        assert!(parse_int("1", 512).is_err());
+    }
+}
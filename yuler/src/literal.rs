@@ -3,6 +3,7 @@ use std::io::Write;
 use anyhow::{anyhow, Result};
 use lazy_static::lazy_static;
 use literals::{ASCIILiteral, NumberLiteral};
+use primitive_types::U256;
 use regex::Regex;
 
 use self::literals::HexNumberLiteral;
@@ -18,10 +19,15 @@ pub enum Literal {
     Number(NumberLiteral),
     HexNumber(HexNumberLiteral),
     ASCII(ASCIILiteral),
+    Hex(HexLiteral),
 }
 
 impl Literal {
     pub fn int_number(s: u64) -> Result<Self> {
+        Ok(Self::Number(NumberLiteral(s.into())))
+    }
+
+    pub fn u256_number(s: U256) -> Result<Self> {
         Ok(Self::Number(NumberLiteral(s)))
     }
 
@@ -41,21 +47,58 @@ impl Literal {
         Ok(Self::ASCII(ASCIILiteral(s)))
     }
 
+    /// Hex string literal, like `hex"abcd"`.
+    pub fn hex(s: impl Into<String>) -> Result<Self> {
+        Ok(Self::Hex(HexLiteral::new(s)?))
+    }
+
     pub fn write(&self, w: &mut impl Write) -> Result<()> {
         match self {
             Self::Number(v) => v.write(w),
             Self::HexNumber(v) => v.write(w),
             Self::ASCII(v) => v.write(w),
+            Self::Hex(v) => v.write(w),
         }
     }
 
+    /// Value of number literal if it fits in `u64`.
     pub fn as_number(&self) -> Option<u64> {
+        match self {
+            Literal::Number(n) if n.0 <= U256::from(u64::MAX) => Some(n.0.low_u64()),
+            _ => None,
+        }
+    }
+
+    /// Value of number or hex number literal.
+    pub fn as_u256(&self) -> Option<U256> {
         match self {
             Literal::Number(n) => Some(n.0),
+            Literal::HexNumber(n) => U256::from_str_radix(n.0.strip_prefix("0x")?, 16).ok(),
             _ => None,
         }
     }
 
+    /// Word of literal as Yul reads it, string literal of at most 32 bytes is left aligned.
+    pub fn as_word(&self) -> Option<U256> {
+        let bytes = match self {
+            Literal::Number(_) | Literal::HexNumber(_) => return self.as_u256(),
+            Literal::ASCII(s) => s.0.as_bytes().to_vec(),
+            Literal::Hex(h) => (0..h.0.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(h.0.get(i..i + 2)?, 16).ok())
+                .collect::<Option<Vec<u8>>>()?,
+        };
+
+        if bytes.len() > 32 {
+            return None;
+        }
+
+        let mut word = [0u8; 32];
+        word[..bytes.len()].copy_from_slice(&bytes);
+
+        Some(U256::from_big_endian(&word))
+    }
+
     pub fn as_ascii(&self) -> Option<&str> {
         match self {
             Literal::ASCII(n) => Some(&n.0),
@@ -68,13 +111,19 @@ pub mod literals {
     use std::io::Write;
 
     use anyhow::Result;
+    use primitive_types::U256;
 
     #[derive(Debug, Clone)]
-    pub struct NumberLiteral(pub(crate) u64);
+    pub struct NumberLiteral(pub(crate) U256);
 
     impl NumberLiteral {
+        /// Number beyond `u64` is written in hex.
         pub fn write(&self, w: &mut impl Write) -> Result<()> {
-            w.write_all(format!("{}", self.0).as_bytes())?;
+            if self.0 > U256::from(u64::MAX) {
+                w.write_all(format!("{:#x}", self.0).as_bytes())?;
+            } else {
+                w.write_all(format!("{}", self.0).as_bytes())?;
+            }
             Ok(())
         }
     }
@@ -122,6 +171,8 @@ impl HexLiteral {
 
 #[cfg(test)]
 mod tests {
+    use primitive_types::U256;
+
     use crate::{HexLiteral, Literal};
 
     #[test]
@@ -135,6 +186,19 @@ mod tests {
         assert_eq!(res, b"123");
     }
 
+    #[test]
+    fn test_u256_number() {
+        let number = Literal::u256_number(U256::MAX).unwrap();
+
+        let mut res = Vec::new();
+
+        number.write(&mut res).unwrap();
+
+        assert_eq!(res, format!("0x{}", "f".repeat(64)).as_bytes());
+        assert_eq!(number.as_number(), None);
+        assert_eq!(number.as_u256(), Some(U256::MAX));
+    }
+
     #[test]
     fn test_int_number() {
         let number = Literal::hex_number("0x123").unwrap();
@@ -146,6 +210,18 @@ mod tests {
         assert_eq!(res, b"0x123");
     }
 
+    #[test]
+    fn test_string_value() {
+        let ascii = Literal::ascii("ab").unwrap();
+        let hex = Literal::hex("6162").unwrap();
+
+        let value = U256::from(0x6162) << 240;
+
+        assert_eq!(ascii.as_word(), Some(value));
+        assert_eq!(hex.as_word(), Some(value));
+        assert_eq!(ascii.as_u256(), None);
+    }
+
     #[test]
     fn test_ascii() {
         let number = Literal::ascii("abcd").unwrap();
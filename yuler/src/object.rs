@@ -43,11 +43,11 @@ impl Object {
         self.code.write(w)?;
 
         for v in &self.data {
+            w.write_end()?;
             w.write_str("data ")?;
             v.name.write_qoute(w)?;
             w.write_str(" ")?;
             v.data.write(&mut w.w)?;
-            w.write_end()?;
         }
         w.write_end()?;
 
@@ -20,11 +20,16 @@ impl Switch {
         self.cond.write(w)?;
         w.write_end()?;
 
-        for v in &self.cases {
+        for (i, v) in self.cases.iter().enumerate() {
             v.write(w)?;
+
+            if i + 1 != self.cases.len() || self.default.is_some() {
+                w.write_end()?;
+            }
         }
 
         if let Some(v) = &self.default {
+            w.write_str("default ")?;
             v.write(w)?;
         }
 
@@ -51,3 +56,34 @@ impl CaseBlock {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod switch_tests {
+    use crate::{Block, CaseBlock, Ident, Literal, Statement, Switch, Writer};
+
+    #[test]
+    fn test_switch() {
+        let sw = Switch {
+            cond: Ident::new("a").unwrap().into(),
+            cases: vec![
+                CaseBlock {
+                    cond: Literal::int_number(0).unwrap(),
+                    block: Block(vec![Statement::Break]),
+                },
+                CaseBlock {
+                    cond: Literal::int_number(1).unwrap(),
+                    block: Block(vec![Statement::Continue]),
+                },
+            ],
+            default: Some(Block(vec![Statement::Leave])),
+        };
+
+        let mut res = Writer::new(Vec::new(), "  ");
+        sw.write(&mut res).unwrap();
+
+        assert_eq!(
+            String::from_utf8(res.w).unwrap(),
+            "switch a\ncase 0 {\n  break\n}\ncase 1 {\n  continue\n}\ndefault {\n  leave\n}"
+        )
+    }
+}
@@ -4,11 +4,11 @@ use std::{
 };
 
 use anyhow::{anyhow, Result};
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use llvm2yul::{Compiler, Config};
 use yuler::Writer;
 
-use crate::{LLVMIRType, Version};
+use crate::{DataLocation, EvmVersion, IntSemantics, LLVMIRType, UnwindMode, Version};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -31,6 +31,23 @@ pub struct Args {
     #[arg(long, default_value = "bitcode")]
     pub llvm_ir_type: LLVMIRType,
 
+    #[arg(long, default_value = "exact")]
+    pub int_semantics: IntSemantics,
+
+    #[arg(long, default_value = "cancun")]
+    pub evm_version: EvmVersion,
+
+    #[arg(long, default_value = "abort")]
+    pub unwind: UnwindMode,
+
+    /// Map address space to data location, like `1=storage`.
+    #[arg(long, value_parser = parse_address_space)]
+    pub address_space: Vec<(u32, DataLocation)>,
+
+    /// Start of memory region reserved for mutable globals.
+    #[arg(long, default_value_t = 0x80)]
+    pub static_base: u64,
+
     pub input: PathBuf,
 }
 
@@ -39,7 +56,16 @@ impl Args {
         let yul_output_dir = self.output_dir.join("yuls");
         fs::create_dir_all(&yul_output_dir)?;
 
-        let config = Config::default().basic_types(&self.basic_type_symbols);
+        let mut config = Config::default()
+            .basic_types(&self.basic_type_symbols)
+            .int_semantics(self.int_semantics.into())
+            .evm_version(self.evm_version.into())
+            .unwind(self.unwind.into())
+            .static_base(self.static_base);
+
+        for (addr_space, location) in self.address_space {
+            config = config.address_space(addr_space, location.into());
+        }
 
         let mut compiler = Compiler::new(config);
 
@@ -61,6 +87,16 @@ impl Args {
     }
 }
 
+fn parse_address_space(s: &str) -> Result<(u32, DataLocation)> {
+    let (addr_space, location) = s
+        .split_once('=')
+        .ok_or(anyhow!("Address space must be like `1=storage`"))?;
+
+    let location = DataLocation::from_str(location, true).map_err(|e| anyhow!("{e}"))?;
+
+    Ok((addr_space.parse()?, location))
+}
+
 fn split_entry(name: &str) -> Result<(&str, &str)> {
     let s: Vec<&str> = name.split('=').collect();
 
@@ -19,3 +19,73 @@ impl LLVMIRType {
         matches!(self, Self::Bitcode)
     }
 }
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum IntSemantics {
+    Wide,
+    Exact,
+}
+
+impl From<IntSemantics> for llvm2yul::IntSemantics {
+    fn from(value: IntSemantics) -> Self {
+        match value {
+            IntSemantics::Wide => Self::Wide,
+            IntSemantics::Exact => Self::Exact,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum EvmVersion {
+    Paris,
+    Shanghai,
+    Cancun,
+    Prague,
+}
+
+impl From<EvmVersion> for llvm2yul::EvmVersion {
+    fn from(value: EvmVersion) -> Self {
+        match value {
+            EvmVersion::Paris => Self::Paris,
+            EvmVersion::Shanghai => Self::Shanghai,
+            EvmVersion::Cancun => Self::Cancun,
+            EvmVersion::Prague => Self::Prague,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum UnwindMode {
+    Abort,
+    Cleanup,
+}
+
+impl From<UnwindMode> for llvm2yul::UnwindMode {
+    fn from(value: UnwindMode) -> Self {
+        match value {
+            UnwindMode::Abort => Self::Abort,
+            UnwindMode::Cleanup => Self::Cleanup,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Debug)]
+pub enum DataLocation {
+    Memory,
+    Storage,
+    Transient,
+    Calldata,
+    Code,
+}
+
+impl From<DataLocation> for llvm2yul::DataLocation {
+    fn from(value: DataLocation) -> Self {
+        match value {
+            DataLocation::Memory => Self::Memory,
+            DataLocation::Storage => Self::Storage,
+            DataLocation::Transient => Self::Transient,
+            DataLocation::Calldata => Self::Calldata,
+            DataLocation::Code => Self::Code,
+        }
+    }
+}